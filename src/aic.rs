@@ -0,0 +1,394 @@
+use std::collections::*;
+use crate::sudoku_board::*;
+use crate::deduction::*;
+use crate::data::*;
+
+const MAX_NODES:usize = 20;
+
+/**
+ * A node in an alternating inference chain: a value in one cell, or a value in a group of cells
+ * (the intersection of a square and a row or col). A group node is true if the value is in one of its cells.
+ */
+struct Node {
+    cells: Vec<usize>,
+    bits: CellBits,
+    seen: CellBits,         // the cells which see all cells of this node
+    value_mask: usize,
+}
+
+/**
+ * The graph of all nodes and their links
+ * strong: at least one of both nodes is true. weak: at most one of both nodes is true.
+ */
+struct Graph {
+    nodes: Vec<Node>,
+    strong: Vec<Vec<usize>>,
+    weak: Vec<Vec<usize>>,
+    value_bits: Vec<CellBits>,      // per value the cells in which it can still be placed
+}
+
+/**
+ * Alternating Inference Chains and Nice Loops, also with grouped nodes.
+ * A chain alternates strong and weak links between nodes:  if the first node is false, the last node is true.
+ * AIC: the first or the last node is true. Every candidate which conflicts with both is removed.
+ * Discontinuous nice loop: the chain ends in the node it started with. If "false" leads to "true" the node is placed,
+ * if "true" leads to "false" the node is removed.
+ * Continuous nice loop: the last node has a weak link to the first one. Then every weak link of the loop is strong as well,
+ * and all candidates which conflict with both ends of a weak link are removed.
+ */
+pub fn find_aic(board:&SudokuBoard) -> Vec<Deduction> {
+    let graph = Graph::new(board);
+    let mut result:Vec<Deduction> = vec![];
+    for start in 0..graph.nodes.len() {
+        // start with "start is false", the reached nodes are true
+        let parents = graph.search(start, false);
+        for end in 0..graph.nodes.len() {
+            if parents[end * 2 + 1] == usize::MAX {
+                continue;
+            }
+            let chain = graph.chain(&parents, start * 2, end * 2 + 1);
+            if !graph.is_valid(&chain) {
+                continue;
+            }
+            let mut deduction = if end == start {
+                graph.placement(start)
+            } else if graph.weak[end].contains(&start) && chain.len() >= 4 {
+                graph.continuous_loop(board, &chain)
+            } else {
+                graph.aic(board, start, end)
+            };
+            if deduction.is_empty() {
+                continue;
+            }
+            graph.describe(&mut deduction, &chain);
+            if !result.iter().any(|d| d.placements == deduction.placements && d.eliminations == deduction.eliminations) {
+                result.push(deduction);
+            }
+        }
+        // start with "start is true", if it leads to "start is false" it can be removed
+        let parents = graph.search(start, true);
+        if parents[start * 2] != usize::MAX && graph.nodes[start].cells.len() == 1 {
+            let chain = graph.chain(&parents, start * 2 + 1, start * 2);
+            if !graph.is_valid(&chain) {
+                continue;
+            }
+            let node = &graph.nodes[start];
+            let mut deduction = Deduction::new("Discontinuous Nice Loop");
+            deduction.eliminate(node.cells[0], node.value_mask);
+            graph.describe(&mut deduction, &chain);
+            if !result.iter().any(|d| d.placements == deduction.placements && d.eliminations == deduction.eliminations) {
+                result.push(deduction);
+            }
+        }
+    }
+    result
+}
+
+impl Graph {
+    fn new(board:&SudokuBoard) -> Graph {
+        let peers = peer_bits(board);
+        let mut nodes = vec![];
+        for cell in board.all_cells() {
+            let candidates = cell.get_candidates();
+            for value in (0..CELL_SIZE).filter(|v| candidates & (1 << v) != 0) {
+                nodes.push(Node::new(vec![cell.get_idx()], 1 << value, &peers));
+            }
+        }
+        for square in &board.squares {
+            for line in board.all_lines() {
+                let segment = board.intersection(square, line);
+                for value in 0..CELL_SIZE {
+                    let cells:Vec<usize> = segment.iter()
+                        .filter(|c| c.get_candidates() & (1 << value) != 0)
+                        .map(|c| c.get_idx())
+                        .collect();
+                    if cells.len() >= 2 {
+                        nodes.push(Node::new(cells, 1 << value, &peers));
+                    }
+                }
+            }
+        }
+        let value_bits = value_bits(board);
+        let mut strong = vec![vec![]; nodes.len()];
+        let mut weak = vec![vec![]; nodes.len()];
+        for a in 0..nodes.len() {
+            for b in a + 1..nodes.len() {
+                let (na, nb) = (&nodes[a], &nodes[b]);
+                let linked = if na.value_mask == nb.value_mask {
+                    // same value: the cells of both nodes see each other
+                    na.bits & nb.bits == 0 && nb.bits & !na.seen == 0
+                } else {
+                    // different values in the same cell
+                    na.cells.len() == 1 && na.bits == nb.bits
+                };
+                if linked {
+                    weak[a].push(b);
+                    weak[b].push(a);
+                }
+            }
+        }
+        // strong links: the only two values of a cell
+        for cell in board.all_cells() {
+            if cell.get_candidates().count_ones() == 2 {
+                let pair:Vec<usize> = (0..nodes.len()).filter(|n| nodes[*n].cells == vec![cell.get_idx()]).collect();
+                strong[pair[0]].push(pair[1]);
+                strong[pair[1]].push(pair[0]);
+            }
+        }
+        // strong links: two nodes which together hold all places of a value within a row, col or square
+        for unit in board.all_logic_squares() {
+            let unit_bits = to_bits(unit.get_cells().iter().map(|c| c.get_idx()));
+            for (value, bits) in value_bits.iter().enumerate() {
+                let places = bits & unit_bits;
+                let inside:Vec<usize> = (0..nodes.len())
+                    .filter(|n| nodes[*n].value_mask == 1 << value && nodes[*n].bits & !places == 0)
+                    .collect();
+                for i in 0..inside.len() {
+                    for j in i + 1..inside.len() {
+                        let (a, b) = (inside[i], inside[j]);
+                        if nodes[a].bits & nodes[b].bits == 0 && nodes[a].bits | nodes[b].bits == places && !strong[a].contains(&b) {
+                            strong[a].push(b);
+                            strong[b].push(a);
+                        }
+                    }
+                }
+            }
+        }
+        Graph { nodes, strong, weak, value_bits }
+    }
+
+    /**
+     * Breadth first search over the states node * 2 + (1 if true).
+     * From a false node a strong link makes the next node true, from a true node a weak link makes the next one false.
+     * Return the parent state of every reached state, usize::MAX if not reached
+     */
+    fn search(&self, start:usize, on:bool) -> Vec<usize> {
+        let first = start * 2 + on as usize;
+        let mut parents = vec![usize::MAX; self.nodes.len() * 2];
+        let mut todo = VecDeque::new();
+        todo.push_back((first, 1));
+        while let Some((state, length)) = todo.pop_front() {
+            if length >= MAX_NODES {
+                continue;
+            }
+            let (node, on) = (state / 2, state % 2 == 1);
+            let links = if on { &self.weak[node] } else { &self.strong[node] };
+            for next in links {
+                let next_state = next * 2 + (!on) as usize;
+                if parents[next_state] == usize::MAX && next_state != first {
+                    parents[next_state] = state;
+                    todo.push_back((next_state, length + 1));
+                }
+            }
+        }
+        parents
+    }
+
+    // the states of the chain from the first state to the last one, following the parents of the search
+    fn chain(&self, parents:&[usize], first:usize, last:usize) -> Vec<usize> {
+        let mut chain = vec![last];
+        let mut state = last;
+        while state != first {
+            state = parents[state];
+            chain.push(state);
+        }
+        chain.reverse();
+        chain
+    }
+
+    /**
+     * The states of the chain must alternate: a false node is followed by a true one over a strong link,
+     * a true node by a false one over a weak link.
+     * Nodes with the same value may not overlap, and a node may only be used once (except the start of a loop)
+     */
+    fn is_valid(&self, chain:&[usize]) -> bool {
+        let alternating = chain.windows(2).all(|pair| {
+            let (node, on) = (pair[0] / 2, pair[0] % 2 == 1);
+            let (next, next_on) = (pair[1] / 2, pair[1] % 2 == 1);
+            let links = if on { &self.weak[node] } else { &self.strong[node] };
+            on != next_on && links.contains(&next)
+        });
+        if !alternating {
+            return false;
+        }
+        let nodes:Vec<usize> = chain.iter().map(|state| state / 2).collect();
+        let inner = if nodes[0] == nodes[nodes.len() - 1] { &nodes[1..] } else { &nodes[..] };
+        for i in 0..inner.len() {
+            for j in i + 1..inner.len() {
+                let (a, b) = (&self.nodes[inner[i]], &self.nodes[inner[j]]);
+                if inner[i] == inner[j] || a.value_mask == b.value_mask && a.bits & b.bits != 0 {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    // the cells of the chain are the reason, a chain with a group node is a grouped one
+    fn describe(&self, deduction:&mut Deduction, chain:&[usize]) {
+        let mut cells:Vec<usize> = vec![];
+        for state in chain {
+            for cell in &self.nodes[state / 2].cells {
+                if cells.last() != Some(cell) {
+                    cells.push(*cell);
+                }
+            }
+        }
+        deduction.reason_cells = cells;
+        // the units of the links between two cells or groups, a link within one cell has none
+        for link in chain.windows(2) {
+            let (a, b) = (&self.nodes[link[0] / 2], &self.nodes[link[1] / 2]);
+            if a.cells != b.cells {
+                deduction.add_units(&common_units(&[a.cells.clone(), b.cells.clone()].concat()));
+            }
+        }
+        if chain.iter().any(|state| self.nodes[state / 2].cells.len() > 1) {
+            deduction.technique = format!("Grouped {}", deduction.technique);
+        }
+    }
+
+    // "start is false" leads to "start is true": the value is placed
+    fn placement(&self, start:usize) -> Deduction {
+        let node = &self.nodes[start];
+        let mut deduction = Deduction::new("Discontinuous Nice Loop");
+        if node.cells.len() == 1 {
+            deduction.place(node.cells[0], node.value_mask);
+        }
+        deduction
+    }
+
+    // the start or the end of the chain is true
+    fn aic(&self, board:&SudokuBoard, start:usize, end:usize) -> Deduction {
+        let (a, b) = (&self.nodes[start], &self.nodes[end]);
+        let mut deduction = Deduction::new("AIC");
+        if a.value_mask == b.value_mask {
+            let value = a.value_mask.trailing_zeros() as usize;
+            for idx in bits_cells(a.seen & b.seen & self.value_bits[value]) {
+                deduction.eliminate(idx, a.value_mask);
+            }
+        } else if a.cells.len() == 1 && b.cells.len() == 1 {
+            let (ca, cb) = (board.get_cell(a.cells[0]), board.get_cell(b.cells[0]));
+            if ca.get_idx() == cb.get_idx() {
+                let others = ca.get_candidates() & !a.value_mask & !b.value_mask;
+                if others != 0 {
+                    deduction.eliminate(ca.get_idx(), others);
+                }
+            } else if ca.sees(cb) {
+                if ca.get_candidates() & b.value_mask != 0 {
+                    deduction.eliminate(ca.get_idx(), b.value_mask);
+                }
+                if cb.get_candidates() & a.value_mask != 0 {
+                    deduction.eliminate(cb.get_idx(), a.value_mask);
+                }
+            }
+        }
+        deduction
+    }
+
+    // every weak link of the loop (the links from a true to a false state + the link from the end back to the start) becomes strong
+    fn continuous_loop(&self, board:&SudokuBoard, chain:&[usize]) -> Deduction {
+        let mut deduction = Deduction::new("Continuous Nice Loop");
+        let mut weak_links = vec![(chain[chain.len() - 1] / 2, chain[0] / 2)];
+        for pair in chain.windows(2).filter(|pair| pair[0] % 2 == 1) {
+            weak_links.push((pair[0] / 2, pair[1] / 2));
+        }
+        for (p, q) in weak_links {
+            let (a, b) = (&self.nodes[p], &self.nodes[q]);
+            if a.value_mask == b.value_mask {
+                let value = a.value_mask.trailing_zeros() as usize;
+                let in_loop = chain.iter()
+                    .map(|state| &self.nodes[state / 2])
+                    .filter(|node| node.value_mask == a.value_mask)
+                    .fold(0, |acc, node| acc | node.bits);
+                for idx in bits_cells(a.seen & b.seen & self.value_bits[value] & !in_loop) {
+                    deduction.eliminate(idx, a.value_mask);
+                }
+            } else {
+                let cell = board.get_cell(a.cells[0]);
+                let others = cell.get_candidates() & !a.value_mask & !b.value_mask;
+                if others != 0 {
+                    deduction.eliminate(cell.get_idx(), others);
+                }
+            }
+        }
+        deduction
+    }
+}
+
+impl Node {
+    fn new(cells:Vec<usize>, value_mask:usize, peers:&[CellBits]) -> Node {
+        let bits = to_bits(cells.iter().cloned());
+        let seen = cells.iter().fold(!0, |acc, idx| acc & peers[*idx]) & !bits;
+        Node { cells, bits, seen, value_mask }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+
+    #[test]
+    fn test_discontinuous_loop() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // only 4 cells are left: r1c1 {12} r1c5 {23} r5c5 {13} r5c1 {14}
+        let open:[(usize, usize);4] = [(0, 0b0011), (4, 0b0110), (40, 0b0101), (36, 0b1001)];
+        for cell in bref.all_cells() {
+            match open.iter().find(|o| o.0 == cell.get_idx()) {
+                Some((_, mask)) => { cell.reduce(0b111111111 & !*mask).ok(); },
+                None            => cell.set_init_value(9),
+            }
+        }
+        let result = find_aic(bref);
+        let placement = result.iter().find(|d| d.placements == vec![(0, 0b0001)]);
+        assert!(placement.is_some());
+        assert_eq!(placement.unwrap().technique, "Discontinuous Nice Loop");
+        assert_eq!(placement.unwrap().reason_cells, vec![0, 4, 40, 36, 0]);
+    }
+    #[test]
+    fn test_continuous_loop() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // the loop r1c1 {12} r5c1 {14} r5c5 {34} r1c5 {23}, row 1 has a third place for 2 in r1c9 {25}
+        let open:[(usize, usize);5] = [(0, 0b0011), (36, 0b1001), (40, 0b1100), (4, 0b0110), (8, 0b10010)];
+        for cell in bref.all_cells() {
+            match open.iter().find(|o| o.0 == cell.get_idx()) {
+                Some((_, mask)) => { cell.reduce(0b111111111 & !*mask).ok(); },
+                None            => cell.set_init_value(9),
+            }
+        }
+        let result = find_aic(bref);
+        let elimination = result.iter().find(|d| d.eliminations == vec![(8, 0b0010)]);
+        assert!(elimination.is_some());
+        assert_eq!(elimination.unwrap().technique, "Continuous Nice Loop");
+        assert_eq!(elimination.unwrap().reason_cells, vec![0, 36, 40, 4]);
+        assert_eq!(elimination.unwrap().units, vec![9, 4, 13]);
+    }
+    #[test]
+    fn test_aic_end_points() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // value 1: r1c1 = r5c1 - r5c9 = r2c9, so r1c1 or r2c9 contains the value
+        only_at(&bref.cols[0], &[0, 4]);
+        only_at(&bref.cols[8], &[1, 4]);
+        let result = find_aic(bref);
+        let aic = result.iter().find(|d| d.technique == "AIC" && d.reason_cells == vec![0, 36, 44, 17]);
+        assert!(aic.is_some());
+        assert_eq!(aic.unwrap().eliminations, vec![(6, 0b001), (7, 0b001), (10, 0b001), (11, 0b001)]);
+    }
+    #[test]
+    fn test_grouped_aic() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // value 1: r1c1 = r1c78 - r3c9 = r7c9, so r1c1 or r7c9 contains the value
+        only_at(&bref.rows[0], &[0, 6, 7]);
+        only_at(&bref.cols[8], &[2, 6]);
+        let result = find_aic(bref);
+        let aic = result.iter().find(|d| d.reason_cells == vec![0, 6, 7, 26, 62]);
+        assert!(aic.is_some());
+        assert_eq!(aic.unwrap().technique, "Grouped AIC");
+        assert_eq!(aic.unwrap().eliminations, vec![(54, 0b001)]);
+    }
+}
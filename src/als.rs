@@ -0,0 +1,367 @@
+use std::collections::*;
+use crate::sudoku_board::*;
+use crate::sudoku_state::*;
+use crate::deduction::*;
+use crate::data::*;
+
+/**
+ * Almost Locked Set: N cells within one row, col or square with together N+1 possible values.
+ * If one of the values is removed, the other values are locked within these cells.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Als {
+    pub cells: Vec<usize>,
+    pub mask: usize,
+    pub unit: usize,            // the row, col or square of the set, as index in all_logic_squares
+    bits: CellBits,
+    value_bits: Vec<CellBits>,  // per value the cells of the set which can contain it
+    seen: Vec<CellBits>,        // per value the cells outside the set which see all cells of the set with that value
+}
+
+impl Als {
+    fn new(cells:Vec<usize>, mask:usize, unit:usize, board:&SudokuBoard, peers:&[CellBits]) -> Als {
+        let bits = to_bits(cells.iter().cloned());
+        let value_bits:Vec<CellBits> = (0..CELL_SIZE)
+            .map(|value| to_bits(cells.iter().cloned().filter(|idx| board.get_cell(*idx).get_candidates() & (1 << value) != 0)))
+            .collect();
+        let seen = value_bits.iter()
+            .map(|vb| if *vb == 0 { 0 } else { bits_cells(*vb).iter().fold(!0, |acc, idx| acc & peers[*idx]) & !bits })
+            .collect();
+        Als { cells, mask, unit, bits, value_bits, seen }
+    }
+    pub fn contains(&self, idx:usize) -> bool {
+        self.bits & (1 as CellBits) << idx != 0
+    }
+    /**
+     * The restricted common values with another set: the value is in both sets and all cells with 
+     * the value in one set see all cells with the value in the other set. It can only be in one of both sets.
+     */
+    pub fn restricted_common(&self, other:&Als) -> usize {
+        if self.bits & other.bits != 0 {
+            return 0;
+        }
+        (0..CELL_SIZE)
+            .filter(|v| self.mask & other.mask & (1 << v) != 0 && other.value_bits[*v] & !self.seen[*v] == 0)
+            .fold(0, |mask, v| mask | (1 << v))
+    }
+}
+
+// all almost locked sets of the board, each set only once
+pub fn find_als(board:&SudokuBoard) -> Vec<Als> {
+    let peers = peer_bits(board);
+    let mut result:Vec<Als> = vec![];
+    let mut found:HashSet<Vec<usize>> = HashSet::new();
+    for (unit, row_col_square) in board.all_logic_squares().iter().enumerate() {
+        let open:Vec<usize> = row_col_square.get_cells().iter().filter(|c| c.get_candidates() != 0).map(|c| c.get_idx()).collect();
+        for size in 1..open.len() {
+            for cells in combinations(&open, size) {
+                let mask = cells.iter().fold(0, |mask, idx| mask | board.get_cell(*idx).get_candidates());
+                if mask.count_ones() as usize == size + 1 && found.insert(cells.clone()) {
+                    result.push(Als::new(cells, mask, unit, board, &peers));
+                }
+            }
+        }
+    }
+    result
+}
+
+/**
+ * ALS-XZ: two sets A and B with a restricted common value X. X is in A or B (or none), so one of them is locked.
+ * A value Z in both sets will be in A or B, and can not be placed in a cell which sees all Z cells of both sets.
+ * With two restricted common values both sets are locked, and each of their values is removed from the cells which see all its cells in that set.
+ */
+pub fn find_als_xz(board:&SudokuBoard) -> Vec<Deduction> {
+    let all_als = find_als(board);
+    let board_bits = value_bits(board);
+    let mut result:Vec<Deduction> = vec![];
+    for pair in combinations(&(0..all_als.len()).collect::<Vec<usize>>(), 2) {
+        let (a, b) = (&all_als[pair[0]], &all_als[pair[1]]);
+        let rcc = a.restricted_common(b);
+        if rcc == 0 || rcc.count_ones() > 2 {
+            continue;
+        }
+        let mut deduction = als_deduction("ALS-XZ", &[a, b]);
+        for value in (0..CELL_SIZE).filter(|v| a.mask & b.mask & (1 << v) != 0) {
+            if rcc.count_ones() == 1 && rcc == 1 << value {
+                continue;
+            }
+            eliminate(&mut deduction, board_bits[value] & a.seen[value] & b.seen[value] & !b.bits, value);
+        }
+        if rcc.count_ones() == 2 {
+            deduction.technique = "ALS-XZ doubly linked".to_string();
+            for (set, other) in [(a, b), (b, a)].iter() {
+                for value in (0..CELL_SIZE).filter(|v| set.mask & !rcc & (1 << v) != 0) {
+                    eliminate(&mut deduction, board_bits[value] & set.seen[value] & !other.bits, value);
+                }
+            }
+        }
+        push_unique(&mut result, deduction);
+    }
+    result
+}
+
+/**
+ * ALS-XY-Wing: a pivot set C with a restricted common value X with set A and Y with set B.
+ * If A is not locked C has X, so C is locked without Y and B is locked. One of A and B is locked,
+ * so a value Z in both A and B can not be placed in a cell which sees all Z cells of both sets.
+ */
+pub fn find_als_xy_wing(board:&SudokuBoard) -> Vec<Deduction> {
+    let all_als = find_als(board);
+    let board_bits = value_bits(board);
+    let mut result:Vec<Deduction> = vec![];
+    for pivot in &all_als {
+        let wings:Vec<(&Als, usize)> = all_als.iter()
+            .map(|als| (als, pivot.restricted_common(als)))
+            .filter(|(_, rcc)| *rcc != 0)
+            .collect();
+        for pair in combinations(&(0..wings.len()).collect::<Vec<usize>>(), 2) {
+            let ((a, a_rcc), (b, b_rcc)) = (wings[pair[0]], wings[pair[1]]);
+            if a.bits & b.bits != 0 {
+                continue;
+            }
+            // choose X and Y different, Z may be neither of them
+            for x in (0..CELL_SIZE).filter(|v| a_rcc & (1 << v) != 0) {
+                for y in (0..CELL_SIZE).filter(|v| *v != x && b_rcc & (1 << v) != 0) {
+                    let mut deduction = als_deduction("ALS-XY-Wing", &[a, b, pivot]);
+                    for z in (0..CELL_SIZE).filter(|v| *v != x && *v != y && a.mask & b.mask & (1 << v) != 0) {
+                        eliminate(&mut deduction, board_bits[z] & a.seen[z] & b.seen[z] & !b.bits & !pivot.bits, z);
+                    }
+                    push_unique(&mut result, deduction);
+                }
+            }
+        }
+    }
+    result
+}
+
+/**
+ * Death Blossom: a stem cell and for each of its values a set (petal) in which all cells with that value see the stem.
+ * Whatever value the stem gets, the petal for that value is locked. A value Z in all petals (and not in the stem)
+ * can not be placed in a cell which sees all Z cells of all petals.
+ */
+pub fn find_death_blossom(board:&SudokuBoard) -> Vec<Deduction> {
+    let all_als = find_als(board);
+    let board_bits = value_bits(board);
+    let mut result:Vec<Deduction> = vec![];
+    for stem in board.all_cells() {
+        let stem_mask = stem.get_candidates();
+        if stem_mask.count_ones() < 2 || stem_mask.count_ones() > 3 {
+            continue;
+        }
+        let idx = stem.get_idx();
+        let stem_values:Vec<usize> = (0..CELL_SIZE).filter(|v| stem_mask & (1 << v) != 0).collect();
+        for z in (0..CELL_SIZE).filter(|v| stem_mask & (1 << v) == 0) {
+            let petals:Vec<Vec<&Als>> = stem_values.iter()
+                .map(|v| all_als.iter()
+                    .filter(|als| !als.contains(idx) && als.mask & (1 << z) != 0 && als.seen[*v] & (1 as CellBits) << idx != 0)
+                    .collect())
+                .collect();
+            let mut chosen = vec![];
+            blossom(&petals, &mut chosen, board_bits[z], z, idx, &mut result);
+        }
+    }
+    result
+}
+
+/**
+ * Sue de Coq: two or three cells in the intersection of a square and a row or col with together at least two values more than cells.
+ * Together with a set of cells in the rest of the line and a set in the rest of the square, without common values,
+ * there are as many cells as values. So each value is placed exactly once: the values of the line set and the intersection values
+ * not in the square set are within the line, the values of the square set and the intersection values not in the line set within the square.
+ */
+pub fn find_sue_de_coq(board:&SudokuBoard) -> Vec<Deduction> {
+    let mut result:Vec<Deduction> = vec![];
+    for (s, square) in board.squares.iter().enumerate() {
+        for (l, line) in board.all_lines().into_iter().enumerate() {
+            let segment:Vec<usize> = board.intersection(square, line).iter()
+                .filter(|c| c.get_candidates() != 0)
+                .map(|c| c.get_idx())
+                .collect();
+            if segment.len() < 2 {
+                continue;
+            }
+            let open_outside = |unit:&dyn RowColSquare| -> Vec<usize> {
+                unit.get_cells().iter()
+                    .filter(|c| c.get_candidates() != 0 && !(square.contains(c) && line.contains(c)))
+                    .map(|c| c.get_idx())
+                    .collect()
+            };
+            let (line_rest, square_rest) = (open_outside(line), open_outside(square));
+            let line_sets = candidate_sets(board, &line_rest);
+            let square_sets = candidate_sets(board, &square_rest);
+            for size in 2..=segment.len() {
+                for cells in combinations(&segment, size) {
+                    let mask = candidates(board, &cells);
+                    if (mask.count_ones() as usize) < size + 2 {
+                        continue;
+                    }
+                    for (line_cells, line_mask) in &line_sets {
+                        for (square_cells, square_mask) in &square_sets {
+                            let all_mask = mask | line_mask | square_mask;
+                            if line_mask & square_mask != 0 || all_mask.count_ones() as usize != size + line_cells.len() + square_cells.len() {
+                                continue;
+                            }
+                            let mut deduction = Deduction::new("Sue de Coq");
+                            deduction.units = vec![l, 2 * CELL_SIZE + s];
+                            deduction.reason_cells = [&cells[..], &line_cells[..], &square_cells[..]].concat();
+                            let in_line = line_mask | (mask & !square_mask);
+                            let in_square = square_mask | (mask & !line_mask);
+                            for (unit, unit_mask) in [(line, in_line), (square as &dyn RowColSquare, in_square)].iter() {
+                                for cell in unit.get_cells() {
+                                    let idx = cell.get_idx();
+                                    if cell.get_candidates() & unit_mask != 0 && !deduction.reason_cells.contains(&idx) {
+                                        deduction.eliminate(idx, cell.get_candidates() & unit_mask);
+                                    }
+                                }
+                            }
+                            push_unique(&mut result, deduction);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    result
+}
+
+// all non empty sets of the given cells with their values, at most 3 cells
+fn candidate_sets(board:&SudokuBoard, cells:&[usize]) -> Vec<(Vec<usize>, usize)> {
+    let mut result = vec![];
+    for size in 1..=cells.len().min(3) {
+        for set in combinations(cells, size) {
+            let mask = candidates(board, &set);
+            result.push((set, mask));
+        }
+    }
+    result
+}
+fn candidates(board:&SudokuBoard, cells:&[usize]) -> usize {
+    cells.iter().fold(0, |mask, idx| mask | board.get_cell(*idx).get_candidates())
+}
+
+// choose a petal for each stem value as long as there are cells left which see all Z cells
+fn blossom<'a>(petals:&[Vec<&'a Als>], chosen:&mut Vec<&'a Als>, seen:CellBits, z:usize, stem:usize, result:&mut Vec<Deduction>) {
+    if seen == 0 {
+        return;
+    }
+    if chosen.len() == petals.len() {
+        let mut deduction = als_deduction("Death Blossom", chosen);
+        deduction.reason_cells.insert(0, stem);
+        let inside = chosen.iter().fold(0, |acc, als| acc | als.bits);
+        eliminate(&mut deduction, seen & !inside & !((1 as CellBits) << stem), z);
+        push_unique(result, deduction);
+        return;
+    }
+    for petal in &petals[chosen.len()] {
+        if chosen.iter().any(|als| als.bits & petal.bits != 0) {
+            continue;
+        }
+        chosen.push(petal);
+        blossom(petals, chosen, seen & petal.seen[z], z, stem, result);
+        chosen.pop();
+    }
+}
+
+fn als_deduction(name:&str, sets:&[&Als]) -> Deduction {
+    let mut deduction = Deduction::new(name);
+    for als in sets {
+        deduction.reason_cells.extend(als.cells.iter());
+        if !deduction.units.contains(&als.unit) {
+            deduction.units.push(als.unit);
+        }
+    }
+    deduction
+}
+fn eliminate(deduction:&mut Deduction, cells:CellBits, value:usize) {
+    for idx in bits_cells(cells) {
+        deduction.eliminate(idx, 1 << value);
+    }
+}
+fn push_unique(result:&mut Vec<Deduction>, deduction:Deduction) {
+    if !deduction.is_empty() && !result.iter().any(|d| d.eliminations == deduction.eliminations) {
+        result.push(deduction);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+
+    #[test]
+    fn test_als_xz() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // A: r1c1 {12}, B: r5c1 {13} and r5c2 {23}, X = 1 and Z = 2
+        bref.get_cell(0).reduce(0b111111100).ok();
+        bref.get_cell(36).reduce(0b111111010).ok();
+        bref.get_cell(37).reduce(0b111111001).ok();
+        let result = find_als_xz(bref);
+        let xz = result.iter().find(|d| d.reason_cells == vec![0, 36, 37]);
+        assert!(xz.is_some());
+        assert_eq!(xz.unwrap().eliminations, vec![(1, 0b10), (10, 0b10), (19, 0b10), (27, 0b10), (45, 0b10)]);
+        assert_eq!(xz.unwrap().units, vec![0, 4]);
+    }
+    #[test]
+    fn test_als_xz_doubly_linked() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // A: r1c1 {14} and r1c2 {24}, B: r3c1 {25} and r6c1 {15}, restricted common values 1 and 2
+        keep(bref.get_cell(0), 0b01001);
+        keep(bref.get_cell(1), 0b01010);
+        keep(bref.get_cell(18), 0b10010);
+        keep(bref.get_cell(45), 0b10001);
+        let result = find_als_xz(bref);
+        let xz = result.iter().find(|d| d.reason_cells == vec![0, 1, 18, 45]);
+        assert!(xz.is_some());
+        assert_eq!(xz.unwrap().technique, "ALS-XZ doubly linked");
+        let eliminations = &xz.unwrap().eliminations;
+        assert!(eliminations.contains(&(9, 0b11011)));
+        assert!(eliminations.contains(&(27, 0b10001)));
+        assert!(eliminations.contains(&(8, 0b01000)));
+    }
+    #[test]
+    fn test_als_xy_wing() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // C: r1c1 {12}, A: r1c5 {13} with X = 1, B: r5c1 {24} and r5c2 {34} with Y = 2, Z = 3
+        keep(bref.get_cell(0), 0b0011);
+        keep(bref.get_cell(4), 0b0101);
+        keep(bref.get_cell(36), 0b1010);
+        keep(bref.get_cell(37), 0b1100);
+        let result = find_als_xy_wing(bref);
+        let wing = result.iter().find(|d| d.reason_cells == vec![4, 36, 37, 0]);
+        assert!(wing.is_some());
+        assert_eq!(wing.unwrap().eliminations, vec![(1, 0b0100), (40, 0b0100)]);
+    }
+    #[test]
+    fn test_death_blossom() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // stem r1c1 {123} with the petals r1c4 {14}, r1c6 {24} and r2c2 {34}, Z = 4
+        keep(bref.get_cell(0), 0b0111);
+        keep(bref.get_cell(3), 0b1001);
+        keep(bref.get_cell(5), 0b1010);
+        keep(bref.get_cell(10), 0b1100);
+        let result = find_death_blossom(bref);
+        let blossom = result.iter().find(|d| d.reason_cells == vec![0, 3, 5, 10]);
+        assert!(blossom.is_some());
+        assert_eq!(blossom.unwrap().eliminations, vec![(1, 0b1000), (2, 0b1000), (12, 0b1000), (13, 0b1000), (14, 0b1000)]);
+    }
+    #[test]
+    fn test_sue_de_coq() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // r1c1 {123} and r1c2 {124} with r1c5 {12} in the row and r2c1 {34} in the square
+        bref.get_cell(0).reduce(0b111111000).ok();
+        bref.get_cell(1).reduce(0b111110100).ok();
+        bref.get_cell(4).reduce(0b111111100).ok();
+        bref.get_cell(9).reduce(0b111110011).ok();
+        let result = find_sue_de_coq(bref);
+        let sdc = result.iter().find(|d| d.reason_cells == vec![0, 1, 4, 9]);
+        assert!(sdc.is_some());
+        assert_eq!(sdc.unwrap().eliminations, vec![(2, 0b1111), (3, 0b11), (5, 0b11), (6, 0b11), (7, 0b11), (8, 0b11),
+                                                   (10, 0b1100), (11, 0b1100), (18, 0b1100), (19, 0b1100), (20, 0b1100)]);
+    }
+}
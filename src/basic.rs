@@ -0,0 +1,177 @@
+use crate::sudoku_board::*;
+use crate::sudoku_state::*;
+use crate::deduction::*;
+use crate::data::*;
+
+const SUBSET_NAMES:[&str;5] = ["", "Single", "Pair", "Triple", "Quad"];
+
+/**
+ * Hidden single: a value which can only be placed in one cell of the row, col or square
+ */
+pub fn hidden_singles(row_col_square:&dyn RowColSquare, unit:usize) -> Result<Vec<Deduction>, String> {
+    let solved_mask = solved_mask(row_col_square);
+    let mut result = vec![];
+    for value in 0..CELL_SIZE {
+        let value_mask = 1 << value;
+        if solved_mask & value_mask != 0 {
+            continue;
+        }
+        let value_cells = row_col_square.get_value_cells(value_mask);
+        match value_cells.len() {
+            0 => return Err(format!("For value {} no positions anymore in {}", value + 1, row_col_square.get_id())),
+            1 => {
+                let idx = value_cells[0].get_idx();
+                let mut deduction = Deduction::new("Hidden Single");
+                deduction.place(idx, value_mask);
+                deduction.reason_cells.push(idx);
+                deduction.units.push(unit);
+                result.push(deduction);
+            },
+            _ => (),
+        }
+    }
+    Ok(result)
+}
+
+/**
+ * Naked subset: N cells which together can only contain N values.
+ * These values can not be placed in any other cell of this row, col or square.
+ * Note that the masks do not need to be identical: {12},{23},{13} is a naked triple as well
+ */
+pub fn naked_subsets(row_col_square:&dyn RowColSquare, unit:usize, size:usize) -> Result<Vec<Deduction>, String> {
+    let cells = row_col_square.get_cells();
+    let open_cells:Vec<usize> = (0..CELL_SIZE).filter(|n| cells[*n].get_candidates() != 0).collect();
+    let mut result = vec![];
+    for subset in combinations(&open_cells, size) {
+        let values_mask = subset.iter().fold(0, |acc, n| acc | cells[*n].get_candidates());
+        if (values_mask.count_ones() as usize) < size {
+            return Err(format!("In {} cells {} share less than {} values", row_col_square.get_id(),
+                subset.iter().map(|n| cell_name(cells[*n].get_idx())).collect::<Vec<String>>().join(" "), size));
+        }
+        if values_mask.count_ones() as usize != size {
+            continue;
+        }
+        let mut deduction = Deduction::new(&format!("Naked {}", SUBSET_NAMES[size]));
+        deduction.units.push(unit);
+        for n in &open_cells {
+            let idx = cells[*n].get_idx();
+            if subset.contains(n) {
+                deduction.reason_cells.push(idx);
+            } else if cells[*n].get_candidates() & values_mask != 0 {
+                deduction.eliminate(idx, cells[*n].get_candidates() & values_mask);
+            }
+        }
+        if !deduction.is_empty() {
+            result.push(deduction);
+        }
+    }
+    Ok(result)
+}
+
+/**
+ * Hidden subset: N values which can only be placed in the same N cells.
+ * These cells can not contain any other value, so all other possible values are removed
+ */
+pub fn hidden_subsets(row_col_square:&dyn RowColSquare, unit:usize, size:usize) -> Result<Vec<Deduction>, String> {
+    let cells = row_col_square.get_cells();
+    let solved_mask = solved_mask(row_col_square);
+    let open_values:Vec<usize> = (0..CELL_SIZE).filter(|value| solved_mask & (1 << value) == 0).collect();
+    let mut value_cells:Vec<usize> = vec![0;CELL_SIZE];
+    for value in &open_values {
+        for (n, cell) in cells.iter().enumerate() {
+            if cell.get_candidates() & (1 << value) != 0 {
+                value_cells[*value] |= 1 << n;
+            }
+        }
+    }
+    let mut result = vec![];
+    for values in combinations(&open_values, size) {
+        let cell_mask = values.iter().fold(0, |acc, value| acc | value_cells[*value]);
+        let values_mask = values.iter().fold(0, |acc, value| acc | 1 << value);
+        if (cell_mask.count_ones() as usize) < size {
+            return Err(format!("In {} values {} fit in less than {} cells", row_col_square.get_id(), mask_digits(values_mask), size));
+        }
+        if cell_mask.count_ones() as usize != size {
+            continue;
+        }
+        let mut deduction = Deduction::new(&format!("Hidden {}", SUBSET_NAMES[size]));
+        deduction.units.push(unit);
+        for (n, cell) in cells.iter().enumerate() {
+            if cell_mask & (1 << n) == 0 {
+                continue;
+            }
+            deduction.reason_cells.push(cell.get_idx());
+            if cell.get_candidates() & !values_mask != 0 {
+                deduction.eliminate(cell.get_idx(), cell.get_candidates() & !values_mask);
+            }
+        }
+        if !deduction.is_empty() {
+            result.push(deduction);
+        }
+    }
+    Ok(result)
+}
+
+/**
+ * Naked single: a cell with only one possible value left
+ */
+pub fn find_naked_single(board:&SudokuBoard) -> Vec<Deduction> {
+    let mut result = vec![];
+    for cell in board.all_cells() {
+        let candidates = cell.get_candidates();
+        if candidates.count_ones() == 1 {
+            let mut deduction = Deduction::new("Naked Single");
+            deduction.place(cell.get_idx(), candidates);
+            deduction.reason_cells.push(cell.get_idx());
+            result.push(deduction);
+        }
+    }
+    result
+}
+
+// a unit technique on all rows, cols and squares, Err if one of them can not be solved anymore
+pub fn find_in_units<F>(board:&SudokuBoard, finder:F) -> Result<Vec<Deduction>, String>
+    where F:Fn(&dyn RowColSquare, usize) -> Result<Vec<Deduction>, String> {
+    let mut result = vec![];
+    for (unit, row_col_square) in board.all_logic_squares().iter().enumerate() {
+        result.extend(finder(*row_col_square, unit)?);
+    }
+    Ok(result)
+}
+
+// the values which are already solved in the row, col or square
+fn solved_mask(row_col_square:&dyn RowColSquare) -> usize {
+    row_col_square.get_cells().iter().fold(0, |acc, cell| acc | cell.get_resolved_mask())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_naked_single() {
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // r1c1 can only be 5
+        bref.get_cell(0).reduce(0b111101111).ok();
+        let deductions = find_naked_single(bref);
+        assert_eq!(deductions.len(), 1);
+        assert_eq!(deductions[0].placements, vec![(0, 0b000010000)]);
+        assert_eq!(deductions[0].apply(bref), Ok(true));
+        // the value is removed from the peers
+        assert_eq!(bref.get_cell(1).get_candidates(), 0b111101111);
+        assert_eq!(bref.get_cell(10).get_candidates(), 0b111101111);
+        assert_eq!(bref.get_cell(40).get_candidates(), 0b111111111);
+    }
+    #[test]
+    fn test_unit_contradiction() {
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // value 1 can not be placed in row 1 anymore
+        for cell in &bref.rows[0].cells {
+            cell.reduce(0b000000001).ok();
+        }
+        assert!(hidden_singles(&bref.rows[0], 0).is_err());
+        assert!(find_in_units(bref, hidden_singles).is_err());
+    }
+}
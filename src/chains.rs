@@ -0,0 +1,186 @@
+use std::collections::*;
+use crate::sudoku_board::*;
+use crate::deduction::*;
+use crate::single_digit::*;
+use crate::data::*;
+
+const MAX_CHAIN:usize = 16;
+
+/**
+ * X-Chain: for one value a chain of cells, alternating strong links (the value can only be placed in two cells of 
+ * a row, col or square) and weak links (two cells which see each other), starting and ending with a strong link.
+ * If the first cell is not the value, the last cell is. So the value can not be placed in a cell which sees both ends.
+ */
+pub fn find_x_chain(board:&SudokuBoard) -> Vec<Deduction> {
+    let mut result = vec![];
+    for value in 0..CELL_SIZE {
+        let value_mask = 1 << value;
+        let mut strong:Vec<Vec<usize>> = vec![vec![]; CELL_COUNT];
+        for unit in board.all_logic_squares() {
+            if let Some((a, b)) = strong_link(unit, value_mask) {
+                if !strong[a.get_idx()].contains(&b.get_idx()) {
+                    strong[a.get_idx()].push(b.get_idx());
+                    strong[b.get_idx()].push(a.get_idx());
+                }
+            }
+        }
+        let weak:Vec<Vec<usize>> = board.all_cells().iter()
+            .map(|cell| board.get_peers(cell).iter()
+                .filter(|peer| cell.get_candidates() & value_mask != 0 && peer.get_candidates() & value_mask != 0)
+                .map(|peer| peer.get_idx())
+                .collect())
+            .collect();
+        for start in 0..CELL_COUNT {
+            if strong[start].is_empty() {
+                continue;
+            }
+            // a node is a cell and whether it is on (reached by a strong link) or off (reached by a weak link)
+            let next = |idx:usize, on:bool| if on { weak[idx].clone() } else { strong[idx].clone() };
+            for chain in search_chains((start, false), |(idx, on)| next(idx, on).into_iter().map(|n| (n, !on)).collect()) {
+                let (end, on) = chain[chain.len() - 1];
+                if !on || chain.len() < 4 {
+                    continue;
+                }
+                let cells:Vec<usize> = chain.iter().map(|node| node.0).collect();
+                let deduction = chain_deduction("X-Chain", board, &cells, start, end, value_mask);
+                if !deduction.is_empty() && !result.iter().any(|d:&Deduction| d.eliminations == deduction.eliminations) {
+                    result.push(deduction);
+                }
+            }
+        }
+    }
+    result
+}
+
+/**
+ * XY-Chain: a chain of cells with two possible values, each cell sharing a value with the next one.
+ * If the first cell is not Z, the next cell is forced to its other value, etc. up to the last cell which becomes Z. 
+ * So Z can not be placed in a cell which sees both ends.
+ */
+pub fn find_xy_chain(board:&SudokuBoard) -> Vec<Deduction> {
+    let mut result = vec![];
+    let bivalue = |idx:usize| board.get_cell(idx).get_candidates().count_ones() == 2;
+    for start in (0..CELL_COUNT).filter(|idx| bivalue(*idx)) {
+        let start_mask = board.get_cell(start).get_candidates();
+        for z_mask in [start_mask & start_mask.wrapping_neg(), start_mask & (start_mask - 1)].iter() {
+            // a node is a cell and the value it gets if the first cell is not Z
+            let first = (start, start_mask & !z_mask);
+            let next = |(idx, on_mask):(usize, usize)| -> Vec<(usize, usize)> {
+                board.get_peers(board.get_cell(idx)).iter()
+                    .filter(|peer| bivalue(peer.get_idx()) && peer.get_candidates() & on_mask != 0)
+                    .map(|peer| (peer.get_idx(), peer.get_candidates() & !on_mask))
+                    .collect()
+            };
+            for chain in search_chains(first, next) {
+                let (end, on_mask) = chain[chain.len() - 1];
+                if on_mask != *z_mask || chain.len() < 3 {
+                    continue;
+                }
+                let cells:Vec<usize> = chain.iter().map(|node| node.0).collect();
+                let deduction = chain_deduction("XY-Chain", board, &cells, start, end, *z_mask);
+                if !deduction.is_empty() && !result.iter().any(|d:&Deduction| d.eliminations == deduction.eliminations) {
+                    result.push(deduction);
+                }
+            }
+        }
+    }
+    result
+}
+
+/**
+ * Breadth first search from the first node, return the shortest chain to every reachable node.
+ * Chains which visit a cell twice are skipped.
+ */
+fn search_chains<N, F>(first:N, next:F) -> Vec<Vec<N>> 
+    where N: Copy + Eq + std::hash::Hash + CellNode, F: Fn(N) -> Vec<N> {
+    let mut parents:HashMap<N, N> = HashMap::new();
+    let mut todo = VecDeque::new();
+    let mut result = vec![];
+    todo.push_back((first, 1));
+    parents.insert(first, first);
+    while let Some((node, length)) = todo.pop_front() {
+        if length >= MAX_CHAIN {
+            continue;
+        }
+        for n in next(node) {
+            if parents.contains_key(&n) {
+                continue;
+            }
+            parents.insert(n, node);
+            todo.push_back((n, length + 1));
+            let mut chain = vec![n];
+            let mut current = n;
+            while current != first {
+                current = parents[&current];
+                chain.push(current);
+            }
+            chain.reverse();
+            let mut cells:Vec<usize> = chain.iter().map(|c| c.cell()).collect();
+            cells.sort();
+            cells.dedup();
+            if cells.len() == chain.len() {
+                result.push(chain);
+            }
+        }
+    }
+    result
+}
+
+// a node in a chain is always about one cell
+trait CellNode {
+    fn cell(&self) -> usize;
+}
+impl CellNode for (usize, bool) {
+    fn cell(&self) -> usize { self.0 }
+}
+impl CellNode for (usize, usize) {
+    fn cell(&self) -> usize { self.0 }
+}
+
+// remove the value from all cells which see both ends of the chain
+fn chain_deduction(name:&str, board:&SudokuBoard, chain:&[usize], start:usize, end:usize, value_mask:usize) -> Deduction {
+    let mut deduction = Deduction::new(name);
+    deduction.reason_cells = chain.to_vec();
+    for link in chain.windows(2) {
+        deduction.add_units(&common_units(link));
+    }
+    for cell in board.common_peers(&[board.get_cell(start), board.get_cell(end)]) {
+        if cell.get_candidates() & value_mask != 0 && !chain.contains(&cell.get_idx()) {
+            deduction.eliminate(cell.get_idx(), value_mask);
+        }
+    }
+    deduction
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+
+    #[test]
+    fn test_x_chain() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        only_at(&bref.rows[0], &[0, 4]);
+        only_at(&bref.squares[1], &[1, 6]);
+        only_at(&bref.cols[3], &[2, 7]);
+        let result = find_x_chain(bref);
+        assert_eq!(result.len(), 1);
+        assert_eq!(format!("{}", result[0]), "X-Chain in row 1, box 2, col 4: r1c1 - r1c5 - r3c4 - r8c4 => r8c1<>1");
+    }
+    #[test]
+    fn test_xy_chain() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        keep(&bref.rows[0].cells[0], 0b0011);
+        keep(&bref.rows[0].cells[4], 0b0110);
+        keep(&bref.rows[4].cells[4], 0b1100);
+        keep(&bref.rows[4].cells[1], 0b1001);
+        let result = find_xy_chain(bref);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].reason_cells, vec![0, 4, 40, 37]);
+        assert_eq!(result[0].units, vec![0, 13, 4]);
+        assert_eq!(result[0].eliminations, vec![(1, 0b001), (10, 0b001), (19, 0b001), (27, 0b001), (36, 0b001), (45, 0b001)]);
+    }
+}
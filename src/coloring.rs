@@ -0,0 +1,219 @@
+use crate::sudoku_board::*;
+use crate::deduction::*;
+use crate::single_digit::*;
+use crate::data::*;
+
+/**
+ * Simple coloring: for one value, the strong links (rows, cols or squares in which the value can only be placed
+ * in two cells) form chains. Within a chain the cells get two alternating colors: all cells of one color contain the value.
+ * Color wrap: two cells of the same color see each other, so that color is false.
+ * Color trap: a cell which sees both colors can not contain the value.
+ */
+pub fn find_simple_coloring(board:&SudokuBoard) -> Vec<Deduction> {
+    let mut result = vec![];
+    for value in 0..CELL_SIZE {
+        let value_mask = 1 << value;
+        for component in color_components(board, value_mask) {
+            for color in 0..2 {
+                let cells = &component[color];
+                let wrap = cells.iter().any(|a| cells.iter().any(|b| sees(board, *a, *b)));
+                if wrap {
+                    let mut deduction = coloring_deduction("Simple Coloring", &[&component]);
+                    for idx in cells {
+                        deduction.eliminate(*idx, value_mask);
+                    }
+                    result.push(deduction);
+                }
+            }
+            let mut deduction = coloring_deduction("Simple Coloring", &[&component]);
+            for cell in board.all_cells() {
+                let idx = cell.get_idx();
+                if cell.get_candidates() & value_mask != 0
+                    && !component[0].contains(&idx) && !component[1].contains(&idx)
+                    && sees_any(board, idx, &component[0]) && sees_any(board, idx, &component[1]) {
+                    deduction.eliminate(idx, value_mask);
+                }
+            }
+            if !deduction.is_empty() {
+                result.push(deduction);
+            }
+        }
+    }
+    result
+}
+
+/**
+ * Multi-coloring: two chains of the same value, where a cell of color a1 in the first chain sees a cell of color b1
+ * of the second chain. a1 and b1 can not both be true, so a2 or b2 is true.
+ * A cell which sees both a2 and b2 can not contain the value.
+ * If a1 sees both colors of the second chain, a1 is false.
+ */
+pub fn find_multi_coloring(board:&SudokuBoard) -> Vec<Deduction> {
+    let mut result = vec![];
+    for value in 0..CELL_SIZE {
+        let value_mask = 1 << value;
+        let components = color_components(board, value_mask);
+        for first in &components {
+            for second in &components {
+                if std::ptr::eq(first, second) {
+                    continue;
+                }
+                for a in 0..2 {
+                    let sees_b = [ sees_color(board, &first[a], &second[0]), sees_color(board, &first[a], &second[1]) ];
+                    if sees_b[0] && sees_b[1] {
+                        let mut deduction = coloring_deduction("Multi-Coloring", &[first, second]);
+                        for idx in &first[a] {
+                            deduction.eliminate(*idx, value_mask);
+                        }
+                        result.push(deduction);
+                        continue;
+                    }
+                    for b in 0..2 {
+                        if !sees_b[b] {
+                            continue;
+                        }
+                        let (true_a, true_b) = (&first[1 - a], &second[1 - b]);
+                        let mut deduction = coloring_deduction("Multi-Coloring", &[first, second]);
+                        for cell in board.all_cells() {
+                            let idx = cell.get_idx();
+                            if cell.get_candidates() & value_mask != 0
+                                && !true_a.contains(&idx) && !true_b.contains(&idx)
+                                && sees_any(board, idx, true_a) && sees_any(board, idx, true_b) {
+                                deduction.eliminate(idx, value_mask);
+                            }
+                        }
+                        if !deduction.is_empty() && !result.iter().any(|d:&Deduction| d.eliminations == deduction.eliminations) {
+                            result.push(deduction);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    result
+}
+
+/**
+ * The chains of strong links for a value. Per chain the cell indexes of both colors
+ */
+pub fn color_components(board:&SudokuBoard, value_mask:usize) -> Vec<[Vec<usize>;2]> {
+    let mut links:Vec<Vec<usize>> = vec![vec![]; CELL_COUNT];
+    for unit in board.all_logic_squares() {
+        if let Some((a, b)) = strong_link(unit, value_mask) {
+            links[a.get_idx()].push(b.get_idx());
+            links[b.get_idx()].push(a.get_idx());
+        }
+    }
+    let mut colors:Vec<Option<usize>> = vec![None; CELL_COUNT];
+    let mut result = vec![];
+    for start in 0..CELL_COUNT {
+        if links[start].is_empty() || colors[start].is_some() {
+            continue;
+        }
+        let mut component = [vec![], vec![]];
+        let mut todo = vec![start];
+        colors[start] = Some(0);
+        while let Some(idx) = todo.pop() {
+            let color = colors[idx].unwrap();
+            component[color].push(idx);
+            for next in &links[idx] {
+                if colors[*next].is_none() {
+                    colors[*next] = Some(1 - color);
+                    todo.push(*next);
+                }
+            }
+        }
+        result.push(component);
+    }
+    result
+}
+
+fn sees(board:&SudokuBoard, a:usize, b:usize) -> bool {
+    board.get_cell(a).sees(board.get_cell(b))
+}
+fn sees_any(board:&SudokuBoard, idx:usize, cells:&[usize]) -> bool {
+    cells.iter().any(|other| sees(board, idx, *other))
+}
+fn sees_color(board:&SudokuBoard, cells:&[usize], others:&[usize]) -> bool {
+    cells.iter().any(|idx| sees_any(board, *idx, others))
+}
+
+fn coloring_deduction(name:&str, components:&[&[Vec<usize>;2]]) -> Deduction {
+    let mut deduction = Deduction::new(name);
+    for component in components {
+        for color in component.iter() {
+            deduction.reason_cells.extend(color);
+        }
+        // the units with both colors hold the strong links
+        for a in &component[0] {
+            for b in &component[1] {
+                deduction.add_units(&common_units(&[*a, *b]));
+            }
+        }
+    }
+    deduction
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+
+    #[test]
+    fn test_color_trap() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // chain r1c1 - r1c5 - r3c4 - r8c4
+        only_at(&bref.rows[0], &[0, 4]);
+        only_at(&bref.squares[1], &[1, 6]);
+        only_at(&bref.cols[3], &[2, 7]);
+        let components = color_components(bref, 0b001);
+        assert_eq!(components.len(), 1);
+        assert_eq!(components[0][0].len() + components[0][1].len(), 4);
+        let result = find_simple_coloring(bref);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].eliminations, vec![(63, 0b001)]);
+        assert_eq!(result[0].units, vec![0, 19, 12]);
+        assert!(find_multi_coloring(bref).is_empty());
+    }
+    #[test]
+    fn test_color_wrap() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // chain r1c1 - r1c5 - r2c5 - r2c2 - r3c2: r1c1 and r3c2 have the same color and see each other
+        only_at(&bref.rows[0], &[0, 4]);
+        only_at(&bref.cols[4], &[0, 1]);
+        only_at(&bref.rows[1], &[1, 4]);
+        only_at(&bref.cols[1], &[1, 2]);
+        let result = find_simple_coloring(bref);
+        let wrap = result.iter().any(|d| {
+            let mut cells:Vec<usize> = d.eliminations.iter().map(|e| e.0).collect();
+            cells.sort();
+            cells == vec![0, 13, 19]
+        });
+        assert!(wrap);
+    }
+    #[test]
+    fn test_multi_coloring() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // chains r1c1 - r1c5 and r2c6 - r7c6: r1c5 sees r2c6, so r1c1 or r7c6 contains the value
+        only_at(&bref.rows[0], &[0, 4]);
+        only_at(&bref.cols[5], &[1, 6]);
+        assert!(find_simple_coloring(bref).is_empty());
+        let result = find_multi_coloring(bref);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].eliminations, vec![(54, 0b001)]);
+    }
+    #[test]
+    fn test_multi_coloring_sees_both_colors() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // chains r1c1 - r1c5 and r2c4 - r2c6: r1c5 sees both colors of the second chain
+        only_at(&bref.rows[0], &[0, 4]);
+        only_at(&bref.rows[1], &[3, 5]);
+        let result = find_multi_coloring(bref);
+        assert!(result.iter().any(|d| d.eliminations == vec![(4, 0b001)]));
+    }
+}
@@ -73,6 +73,14 @@ fn handle_commands(cmd: &Command, data: &mut AppState) {
         data.do_step();
 
     } else 
+    if  cmd.is(COMMAND_HINT)
+    {
+        data.do_hint();
+    } else 
+    if  cmd.is(COMMAND_RATE)
+    {
+        data.do_rate();
+    } else 
     if  cmd.is(COMMAND_SAVE)
     {
         data.exec_save();
@@ -87,6 +95,7 @@ fn handle_commands(cmd: &Command, data: &mut AppState) {
     {
         let sel = cmd.get(COMMAND_SOLVE);
         println!("Received command solve with id  {:?}", sel   );
+        data.do_solve();
     } else
     if  cmd.is(COMMAND_NUMBER)
     {
@@ -2,6 +2,8 @@ use std::sync::*;
 //use std::vec::Vec;
 use crate::sudoku_board::*;
 use crate::sudoku_state::*;
+use crate::deduction::*;
+use crate::dlx::*;
 //use std::borrow::Borrow;
 use druid::im;
 use druid::*;
@@ -19,6 +21,8 @@ pub const COMMAND_INIT: Selector<String> = Selector::new("sudoku.init");
 pub const COMMAND_SELECT: Selector<String> = Selector::new("sudoku.select");
 pub const COMMAND_SELECTED: Selector<String> = Selector::new("sudoku.selected");
 pub const COMMAND_BACK: Selector<String> = Selector::new("sudoku.back");
+pub const COMMAND_HINT: Selector<String> = Selector::new("sudoku.hint");
+pub const COMMAND_RATE: Selector<String> = Selector::new("sudoku.rate");
 pub const COMMAND_NUMBER: Selector<(RcSudokuCell, usize)> = Selector::new("sudoku.number");
 
 pub const INI_FILE:&str = "data/sudoku.ini";
@@ -52,6 +56,9 @@ pub struct AppState {
     #[data(ignore)]
     pub autoselect_list:im::Vector<String>,
     pub selected: String,
+    #[data(same_fn = "PartialEq::eq")]
+    pub hint: Option<Deduction>,   // the deduction of which the cells are highlighted
+    pub level: Option<usize>,      // the level= key of the selected board in the ini file, typed by hand
 }
 
 impl AppState {
@@ -65,6 +72,8 @@ impl AppState {
             selected:"".into(),
             board_list: im::vector![],
             autoselect_list: im::vector![],
+            hint: None,
+            level: None,
         }
     }
     pub fn init(&mut self) {
@@ -106,8 +115,12 @@ impl AppState {
         let map = ini!(INI_FILE);
         let board = &*self.su_board;
         let sudoku = map.get(&self.selected).unwrap();
+        self.level = None;
     
         for (key, value) in sudoku {
+            if key == "level" {
+                self.level = value.as_ref().and_then(|v| v.trim().parse().ok());
+            }
             if key.starts_with("row"){
                 let rowc = key.chars().nth(3).unwrap();
                 let row = rowc.to_digit(10).unwrap() - 1; 
@@ -120,6 +133,7 @@ impl AppState {
                 } 
             }
         }
+        self.su_state.check_solutions(board);
         self.su_state.reduce_step(board);
         self.message = "Rightclick for manual select".to_string();
     }
@@ -158,10 +172,18 @@ impl AppState {
         let gamestate = self.su_state.get_state();
         ! (GameState::ManualInput == gamestate || GameState::Stepping == gamestate)
     }
+    pub fn isHintDisabled(&self) -> bool {
+        let gamestate = self.su_state.get_state();
+        ! (GameState::ManualInput == gamestate || GameState::Stepping == gamestate)
+    }
+    pub fn isRateDisabled(&self) -> bool {
+        let gamestate = self.su_state.get_state();
+        ! (GameState::ManualInput == gamestate || GameState::Stepping == gamestate)
+            || self.su_state.get_solution_count() != Some(SolutionCount::Unique)
+    }
     pub fn isSolveDisabled(&self) -> bool {
         let gamestate = self.su_state.get_state();
-        // ! (GameState::ManualInput == gamestate || GameState::Stepping == gamestate )
-        true
+        ! (GameState::ManualInput == gamestate || GameState::Stepping == gamestate )
     }
     pub fn isSelectVisible(&self) -> bool {
         let gamestate = self.su_state.get_state();
@@ -172,11 +194,33 @@ impl AppState {
         GameState::Select == gamestate && self.autoselect_list.len() > 0
     }
 
+    pub fn do_hint(&mut self) {
+        let board = & *self.su_board;
+        self.hint = self.su_state.hint(board);
+        self.message = match &self.hint {
+            Some(deduction) => format!("Hint: {}", deduction.technique),
+            None            => "No hint".to_string(),
+        };
+    }
+    // rating runs the techniques until the puzzle is solved, so it is only done on request
+    pub fn do_rate(&mut self) {
+        let board = & *self.su_board;
+        self.message = match (self.su_state.check_rating(board), self.level) {
+            (Some(rating), Some(level)) => format!("Rated: {}, level {} in the ini file", rating, level),
+            (Some(rating), None)        => format!("Rated: {}", rating),
+            (None, _)                   => "Only a puzzle with one solution is rated".to_string(),
+        };
+    }
     pub fn do_step(&mut self) {
+        self.hint = None;
         let state = &mut self.su_state;
         let board = & *self.su_board;
-        match state.resolve_step(board){
-            GameState::Stepping     =>  self.message = "Stepping..".to_string(),
+        let deductions = state.resolve_step(board);
+        match state.get_state(){
+            GameState::Stepping     =>  self.message = match deductions.first() {
+                Some(deduction) => format!("Stepping.. {} ({}x)", deduction.technique, deductions.len()),
+                None            => "Stepping..".to_string(),
+            },
             GameState::Error        =>  self.message = "ERROR".to_string(),
             GameState::Solved       =>  self.message = "RESOLVED!!".to_string(),            
             GameState::ManualInput  =>  self.message = "Manual Input".to_string(),            
@@ -184,7 +228,18 @@ impl AppState {
         }
         //board.show();
     }  
+    pub fn do_solve(&mut self) {
+        self.hint = None;
+        let state = &mut self.su_state;
+        let board = & *self.su_board;
+        match state.solve(board){
+            GameState::Solved       =>  self.message = "SOLVED!!".to_string(),            
+            GameState::Error        =>  self.message = "No solution".to_string(),
+            _                       =>  self.message = "unknown".to_string()
+        }
+    }  
     pub fn do_step_back(&mut self) {
+        self.hint = None;
         let state = &mut self.su_state;
         let board = & *self.su_board;
 
@@ -193,8 +248,10 @@ impl AppState {
     }  
 
     pub fn do_reduce(&mut self) {
+        self.hint = None;
         let state = &mut self.su_state;
         let board = & *self.su_board;
+        state.check_solutions(board);
         match state.reduce_step(board){
             GameState::Error        =>  self.message = "ERROR".to_string(),
             _                       =>  self.message = "Manual Input".to_string(),            
@@ -202,10 +259,12 @@ impl AppState {
         //board.show();
     }  
     pub fn do_restart(&mut self) {
+        self.hint = None;
         let state = &mut self.su_state;
         let board = & *self.su_board;
         board.reset();
         state.reset();
+        self.level = None;
     }
     pub fn autoselect(&mut self) {
         let listref = &self.board_list;
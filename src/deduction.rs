@@ -0,0 +1,135 @@
+use std::fmt;
+use crate::sudoku_board::*;
+use crate::data::*;
+
+/**
+ * The result of a technique: the values to place and the possible values to remove, with the reason.
+ * Finding a deduction does not change the board, apply does.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Deduction {
+    pub technique: String,
+    pub placements: Vec<(usize, usize)>,     // cell index and the bitmask of the value to place
+    pub eliminations: Vec<(usize, usize)>,   // cell index and the bitmask of the values which can not be placed anymore
+    pub reason_cells: Vec<usize>,            // the cell indexes which form the pattern
+    pub units: Vec<usize>,                   // the rows, cols and squares of the pattern, as index in all_logic_squares
+}
+
+impl Deduction {
+    pub fn new(technique:&str) -> Deduction {
+        Deduction {
+            technique: technique.to_string(),
+            placements: vec![],
+            eliminations: vec![],
+            reason_cells: vec![],
+            units: vec![],
+        }
+    }
+    // add an elimination, merging it with an earlier one for the same cell
+    pub fn eliminate(&mut self, idx:usize, mask:usize) {
+        match self.eliminations.iter_mut().find(|e| e.0 == idx) {
+            Some(e) => e.1 |= mask,
+            None    => self.eliminations.push((idx, mask)),
+        }
+    }
+    // add the rows, cols and squares which are not part of the deduction yet
+    pub fn add_units(&mut self, units:&[usize]) {
+        for unit in units {
+            if !self.units.contains(unit) {
+                self.units.push(*unit);
+            }
+        }
+    }
+    pub fn place(&mut self, idx:usize, mask:usize) {
+        if !self.placements.contains(&(idx, mask)) {
+            self.placements.push((idx, mask));
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.placements.is_empty() && self.eliminations.is_empty()
+    }
+    /**
+     * Apply the placements and eliminations on the board, a placed value is removed from the cells which see it
+     * Return true if at least one value is placed or one possible value is removed
+     */
+    pub fn apply(&self, board:&SudokuBoard) -> Result<bool, String> {
+        let mut changed = false;
+        for (idx, mask) in &self.placements {
+            let cell = board.get_cell(*idx);
+            match cell.get_state() {
+                CellState::UnSolved(n) if n & mask == *mask => changed |= cell.set_solved_value(*mask),
+                CellState::Solved(v, _) if v == *mask => (),
+                _ => return Err(format!("Error: {} can not be placed in {:?}", mask_digits(*mask), cell.get_pos())),
+            }
+            for peer in board.get_peers(cell) {
+                changed |= peer.eliminate(*mask)?;
+            }
+        }
+        for (idx, mask) in &self.eliminations {
+            changed |= board.get_cell(*idx).eliminate(*mask)?;
+        }
+        Ok(changed)
+    }
+}
+
+/**
+ * Show the deduction in r1c1 notation, the reason cells in the order of the pattern (or chain):
+ *  X-Chain: r1c1 - r1c5 - r3c4 - r8c4 => r8c1<>1
+ *  Naked Pair in col 2: r1c2 - r5c2 => r3c2<>47
+ */
+impl fmt::Display for Deduction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reasons:Vec<String> = self.reason_cells.iter().map(|idx| cell_name(*idx)).collect();
+        let mut results:Vec<String> = self.placements.iter()
+            .map(|(idx, mask)| format!("{}={}", cell_name(*idx), mask_digits(*mask)))
+            .collect();
+        results.extend(self.eliminations.iter()
+            .map(|(idx, mask)| format!("{}<>{}", cell_name(*idx), mask_digits(*mask))));
+        let units:Vec<String> = self.units.iter().map(|unit| unit_name(*unit)).collect();
+        if units.is_empty() {
+            write!(f, "{}: {} => {}", self.technique, reasons.join(" - "), results.join(", "))
+        } else {
+            write!(f, "{} in {}: {} => {}", self.technique, units.join(", "), reasons.join(" - "), results.join(", "))
+        }
+    }
+}
+
+// the name of a row, col or square by its index in all_logic_squares
+pub fn unit_name(unit:usize) -> String {
+    let kind = match unit / CELL_SIZE {
+        0 => "row",
+        1 => "col",
+        _ => "box",
+    };
+    format!("{} {}", kind, unit % CELL_SIZE + 1)
+}
+
+// the name of the cell in r1c1 notation
+pub fn cell_name(idx:usize) -> String {
+    format!("r{}c{}", idx / CELL_SIZE + 1, idx % CELL_SIZE + 1)
+}
+
+// the values in a bitmask as digits
+pub fn mask_digits(mask:usize) -> String {
+    (0..CELL_SIZE).filter(|n| mask & (1 << n) != 0).map(|n| HEX_DIGITS[n]).collect()
+}
+
+// a set of cells as bits, one bit per cell index
+pub type CellBits = u128;
+
+pub fn to_bits<I:Iterator<Item=usize>>(cells:I) -> CellBits {
+    cells.fold(0, |acc, idx| acc | (1 as CellBits) << idx)
+}
+pub fn bits_cells(bits:CellBits) -> Vec<usize> {
+    (0..CELL_COUNT).filter(|idx| bits & (1 as CellBits) << idx != 0).collect()
+}
+// per cell the cells which it sees
+pub fn peer_bits(board:&SudokuBoard) -> Vec<CellBits> {
+    board.all_cells().iter().map(|cell| to_bits(board.get_peers(cell).iter().map(|p| p.get_idx()))).collect()
+}
+// per value the cells in which it can still be placed
+pub fn value_bits(board:&SudokuBoard) -> Vec<CellBits> {
+    (0..CELL_SIZE)
+        .map(|value| to_bits(board.all_cells().iter().filter(|c| c.get_candidates() & (1 << value) != 0).map(|c| c.get_idx())))
+        .collect()
+}
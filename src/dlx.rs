@@ -0,0 +1,258 @@
+use std::fmt;
+use druid::Data;
+use crate::sudoku_board::*;
+use crate::data::*;
+
+// count the solutions up to this amount
+pub const COUNT_LIMIT:usize = 10;
+
+// the constraints: each cell one value, and each value once per row, col and square
+const COLUMNS:usize = 4 * CELL_COUNT;
+
+// a solution, per cell index the bitmask of its value
+pub type Solution = Vec<usize>;
+
+/**
+ * Dancing Links: Knuth's Algorithm X on the exact cover matrix of the sudoku.
+ * Each matrix row is a value in a cell, each of the 324 columns a constraint.
+ * A solution is a set of rows which covers every column exactly once.
+ * The nodes are stored in vectors, node 0 is the root and nodes 1..=324 are the column headers.
+ */
+pub struct Dlx {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    column: Vec<usize>,
+    size: Vec<usize>,               // per column header the number of rows in it
+    choice: Vec<(usize, usize)>,    // per node the cell index and the value
+}
+
+impl Dlx {
+    /**
+     * The matrix for a board: a solved cell only gets a row for its value, an unsolved cell for every value.
+     * So the possible values found by the logic are not used, it is an independent check.
+     */
+    pub fn new(board:&SudokuBoard) -> Dlx {
+        let mut dlx = Dlx {
+            left: vec![], right: vec![], up: vec![], down: vec![], column: vec![],
+            size: vec![0; COLUMNS + 1],
+            choice: vec![],
+        };
+        for node in 0..=COLUMNS {
+            dlx.left.push(if node == 0 { COLUMNS } else { node - 1 });
+            dlx.right.push(if node == COLUMNS { 0 } else { node + 1 });
+            dlx.up.push(node);
+            dlx.down.push(node);
+            dlx.column.push(node);
+            dlx.choice.push((0, 0));
+        }
+        for cell in board.all_cells() {
+            let idx = cell.get_idx();
+            let solved = cell.get_resolved_mask();
+            for value in (0..CELL_SIZE).filter(|v| solved == 0 || solved == 1 << v) {
+                dlx.add_row(idx, value);
+            }
+        }
+        dlx
+    }
+
+    // add the row for a value in a cell, with one node in each of its 4 constraint columns
+    fn add_row(&mut self, idx:usize, value:usize) {
+        let (r, c) = (idx / CELL_SIZE, idx % CELL_SIZE);
+        let square = (r / 3) * 3 + c / 3;
+        let columns = [
+            idx,
+            CELL_COUNT + r * CELL_SIZE + value,
+            2 * CELL_COUNT + c * CELL_SIZE + value,
+            3 * CELL_COUNT + square * CELL_SIZE + value,
+        ];
+        let first = self.column.len();
+        for (i, col) in columns.iter().enumerate() {
+            let header = col + 1;
+            let node = first + i;
+            self.left.push(if i == 0 { first + 3 } else { node - 1 });
+            self.right.push(if i == 3 { first } else { node + 1 });
+            self.up.push(self.up[header]);
+            self.down.push(header);
+            self.column.push(header);
+            self.choice.push((idx, value));
+            let last = self.up[header];
+            self.down[last] = node;
+            self.up[header] = node;
+            self.size[header] += 1;
+        }
+    }
+
+    /**
+     * Search for solutions, stop when the limit is reached.
+     * Afterwards the matrix is complete again, so it can be searched again.
+     */
+    pub fn solve(&mut self, limit:usize) -> Vec<Solution> {
+        let mut solutions = vec![];
+        let mut rows = vec![];
+        self.search(&mut rows, &mut solutions, limit);
+        solutions
+    }
+
+    fn search(&mut self, rows:&mut Vec<usize>, solutions:&mut Vec<Solution>, limit:usize) {
+        if solutions.len() >= limit {
+            return;
+        }
+        if self.right[0] == 0 {
+            let mut solution = vec![0; CELL_COUNT];
+            for node in rows.iter() {
+                let (idx, value) = self.choice[*node];
+                solution[idx] = 1 << value;
+            }
+            solutions.push(solution);
+            return;
+        }
+        // the column with the fewest rows
+        let mut header = self.right[0];
+        let mut col = header;
+        while col != 0 {
+            if self.size[col] < self.size[header] {
+                header = col;
+            }
+            col = self.right[col];
+        }
+        if self.size[header] == 0 {
+            return;
+        }
+        self.cover(header);
+        let mut row = self.down[header];
+        while row != header {
+            rows.push(row);
+            let mut node = self.right[row];
+            while node != row {
+                self.cover(self.column[node]);
+                node = self.right[node];
+            }
+            self.search(rows, solutions, limit);
+            let mut node = self.left[row];
+            while node != row {
+                self.uncover(self.column[node]);
+                node = self.left[node];
+            }
+            rows.pop();
+            row = self.down[row];
+        }
+        self.uncover(header);
+    }
+
+    fn cover(&mut self, header:usize) {
+        let (l, r) = (self.left[header], self.right[header]);
+        self.right[l] = r;
+        self.left[r] = l;
+        let mut row = self.down[header];
+        while row != header {
+            let mut node = self.right[row];
+            while node != row {
+                let (u, d) = (self.up[node], self.down[node]);
+                self.down[u] = d;
+                self.up[d] = u;
+                self.size[self.column[node]] -= 1;
+                node = self.right[node];
+            }
+            row = self.down[row];
+        }
+    }
+
+    fn uncover(&mut self, header:usize) {
+        let mut row = self.up[header];
+        while row != header {
+            let mut node = self.left[row];
+            while node != row {
+                let (u, d) = (self.up[node], self.down[node]);
+                self.down[u] = node;
+                self.up[d] = node;
+                self.size[self.column[node]] += 1;
+                node = self.left[node];
+            }
+            row = self.up[row];
+        }
+        let (l, r) = (self.left[header], self.right[header]);
+        self.right[l] = header;
+        self.left[r] = header;
+    }
+}
+
+// all solutions of the board, at most limit
+pub fn dlx_solutions(board:&SudokuBoard, limit:usize) -> Vec<Solution> {
+    Dlx::new(board).solve(limit)
+}
+
+#[derive(Data, Clone, PartialEq, Debug)]
+pub enum SolutionCount {
+    NoSolution,
+    Unique,
+    AtLeast(usize),     // more than one solution, the search stopped at the limit or found them all
+}
+
+impl fmt::Display for SolutionCount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolutionCount::NoSolution => write!(f, "no solution"),
+            SolutionCount::Unique     => write!(f, "unique"),
+            SolutionCount::AtLeast(n) => write!(f, "at least {} solutions", n),
+        }
+    }
+}
+
+/**
+ * Count the solutions of the board, but stop at the limit (at least 2, to tell unique and multiple apart)
+ */
+pub fn count_solutions(board:&SudokuBoard, limit:usize) -> SolutionCount {
+    match dlx_solutions(board, limit.max(2)).len() {
+        0 => SolutionCount::NoSolution,
+        1 => SolutionCount::Unique,
+        n => SolutionCount::AtLeast(n),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dlx() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // hard-4-26 has one solution
+        let puzzle = "-2------81-7---4--6----4-9---4-68-7---2---8---6-15-3---3-5----7--1---2-97------6-";
+        for (idx, c) in puzzle.chars().enumerate() {
+            if c != '-' {
+                bref.init_cell(idx / CELL_SIZE, idx % CELL_SIZE, c.to_digit(10).unwrap() as usize);
+            }
+        }
+        let solutions = dlx_solutions(bref, 10);
+        assert_eq!(solutions.len(), 1);
+        for cell in bref.all_cells() {
+            let solved = cell.get_resolved_mask();
+            assert!(solved == 0 || solved == solutions[0][cell.get_idx()]);
+        }
+        for unit in bref.all_logic_squares() {
+            let mask = unit.get_cells().iter().fold(0, |mask, c| mask | solutions[0][c.get_idx()]);
+            assert_eq!(mask, 0b111111111);
+        }
+        // 3 in row 1 twice
+        bref.init_cell(0, 0, 3);
+        bref.init_cell(0, 7, 3);
+        assert_eq!(dlx_solutions(bref, 10).len(), 0);
+    }
+    #[test]
+    fn test_dlx_limit() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // the test example has many solutions
+        bref.init();
+        assert_eq!(dlx_solutions(bref, 10).len(), 10);
+        let mut dlx = Dlx::new(bref);
+        assert_eq!(dlx.solve(1), dlx.solve(1));
+        assert_eq!(count_solutions(bref, 5), SolutionCount::AtLeast(5));
+        assert_eq!(count_solutions(bref, 1), SolutionCount::AtLeast(2));
+        assert_eq!(count_solutions(bref, 1).to_string(), "at least 2 solutions");
+    }
+}
@@ -0,0 +1,204 @@
+use crate::sudoku_board::*;
+use crate::sudoku_state::*;
+use crate::deduction::*;
+use crate::data::*;
+
+const FISH_NAMES:[&str;5] = ["", "", "X-Wing", "Swordfish", "Jellyfish"];
+
+/**
+ * Basic fish: in size base lines (rows or cols) a value can only be placed in the same size cover lines (cols or rows).
+ * The value will be placed in the crossings of base and cover lines, so it can not be placed in the rest of the cover lines.
+ */
+pub fn find_fish(board:&SudokuBoard, size:usize) -> Vec<Deduction> {
+    let lines = board.all_lines();
+    let (rows, cols) = lines.split_at(CELL_SIZE);
+    let mut result = vec![];
+    for value in 0..CELL_SIZE {
+        let value_mask = 1 << value;
+        for (base, cover, base_offset) in [(rows, cols, 0), (cols, rows, CELL_SIZE)].iter() {
+            let positions:Vec<usize> = base.iter().map(|line| cover_positions(*line, cover, value_mask)).collect();
+            let candidates:Vec<usize> = (0..CELL_SIZE)
+                .filter(|i| (2..=size).contains(&(positions[*i].count_ones() as usize)))
+                .collect();
+            for base_set in combinations(&candidates, size) {
+                let cover_set = base_set.iter().fold(0, |acc, i| acc | positions[*i]);
+                if cover_set.count_ones() as usize != size {
+                    continue;
+                }
+                let mut deduction = Deduction::new(FISH_NAMES[size]);
+                deduction.units = fish_units(&base_set, cover_set, *base_offset);
+                for j in 0..CELL_SIZE {
+                    if cover_set & (1 << j) == 0 {
+                        continue;
+                    }
+                    for cell in cover[j].get_value_cells(value_mask) {
+                        if base_set.iter().any(|i| base[*i].contains(&cell)) {
+                            deduction.reason_cells.push(cell.get_idx());
+                        } else {
+                            deduction.eliminate(cell.get_idx(), value_mask);
+                        }
+                    }
+                }
+                if !deduction.is_empty() {
+                    result.push(deduction);
+                }
+            }
+        }
+    }
+    result
+}
+
+/**
+ * Finned and sashimi fish: a fish of which the base lines have extra candidates (the fins) within one square.
+ * Either the fish is true, or one of the fins is true. So the value can only be removed from the cells
+ * in the cover lines which see all fins.
+ */
+pub fn find_finned_fish(board:&SudokuBoard, size:usize) -> Vec<Deduction> {
+    let lines = board.all_lines();
+    let (rows, cols) = lines.split_at(CELL_SIZE);
+    let mut result:Vec<Deduction> = vec![];
+    for value in 0..CELL_SIZE {
+        let value_mask = 1 << value;
+        for (base, cover, base_offset) in [(rows, cols, 0), (cols, rows, CELL_SIZE)].iter() {
+            let positions:Vec<usize> = base.iter().map(|line| cover_positions(*line, cover, value_mask)).collect();
+            // at most 3 fins per base line, as all fins are in one square
+            let candidates:Vec<usize> = (0..CELL_SIZE)
+                .filter(|i| (1..=size + 3).contains(&(positions[*i].count_ones() as usize)))
+                .collect();
+            for base_set in combinations(&candidates, size) {
+                let all_positions = base_set.iter().fold(0, |acc, i| acc | positions[*i]);
+                if all_positions.count_ones() as usize <= size {
+                    continue;
+                }
+                let cover_candidates:Vec<usize> = (0..CELL_SIZE).filter(|j| all_positions & (1 << j) != 0).collect();
+                for cover_list in combinations(&cover_candidates, size) {
+                    let cover_set = cover_list.iter().fold(0, |acc, j| acc | (1 << j));
+                    if base_set.iter().any(|i| positions[*i] & cover_set == 0) {
+                        continue;
+                    }
+                    let mut fins = vec![];
+                    for i in &base_set {
+                        for cell in base[*i].get_value_cells(value_mask) {
+                            if !cover_list.iter().any(|j| cover[*j].contains(&cell)) {
+                                fins.push(cell);
+                            }
+                        }
+                    }
+                    let square = board.square_of(&fins[0]);
+                    if !fins.iter().all(|fin| square.contains(fin)) {
+                        continue;
+                    }
+                    let sashimi = base_set.iter().any(|i| (positions[*i] & cover_set).count_ones() == 1);
+                    let name = format!("{} {}", if sashimi { "Sashimi" } else { "Finned" }, FISH_NAMES[size]);
+                    let mut deduction = Deduction::new(&name);
+                    deduction.units = fish_units(&base_set, cover_set, *base_offset);
+                    for j in &cover_list {
+                        for cell in cover[*j].get_value_cells(value_mask) {
+                            if base_set.iter().any(|i| base[*i].contains(&cell)) {
+                                deduction.reason_cells.push(cell.get_idx());
+                            } else if fins.iter().all(|fin| fin.sees(&cell)) {
+                                deduction.eliminate(cell.get_idx(), value_mask);
+                            }
+                        }
+                    }
+                    for fin in &fins {
+                        deduction.reason_cells.push(fin.get_idx());
+                    }
+                    if !deduction.is_empty() && !result.iter().any(|d| d.eliminations == deduction.eliminations) {
+                        result.push(deduction);
+                    }
+                }
+            }
+        }
+    }
+    result
+}
+
+// bitmask of the cover lines in which the value can be placed within this line
+fn cover_positions(line:&dyn RowColSquare, cover:&[&dyn RowColSquare], value_mask:usize) -> usize {
+    let mut positions = 0;
+    for cell in line.get_value_cells(value_mask) {
+        for (j, cover_line) in cover.iter().enumerate() {
+            if cover_line.contains(&cell) {
+                positions |= 1 << j;
+            }
+        }
+    }
+    positions
+}
+
+// the base lines followed by the cover lines, as index in all_logic_squares: rows first, then cols
+fn fish_units(base_set:&[usize], cover_set:usize, base_offset:usize) -> Vec<usize> {
+    let cover_offset = CELL_SIZE - base_offset;
+    base_set.iter().map(|i| base_offset + i)
+        .chain((0..CELL_SIZE).filter(|j| cover_set & (1 << j) != 0).map(|j| cover_offset + j))
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_x_wing() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // in row 1 and row 5 value 1 can only be placed in col 2 and col 7
+        for r in [0, 4].iter() {
+            for cell in &bref.rows[*r].cells {
+                let col = cell.get_pos().1;
+                if col != 2 && col != 7 {
+                    cell.reduce(0b000000001).ok();
+                }
+            }
+        }
+        let result = find_fish(bref, 2);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].technique, "X-Wing");
+        assert_eq!(result[0].reason_cells.len(), 4);
+        assert_eq!(result[0].eliminations.len(), 14);
+        assert_eq!(result[0].units, vec![0, 4, 10, 15]);
+        assert!(find_fish(bref, 3).is_empty());
+    }
+    #[test]
+    fn test_finned_x_wing() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // in row 1 value 1 can only be placed in col 2, 3 and 7, in row 5 only in col 2 and col 7
+        for (r, keep) in [(0, vec![2, 3, 7]), (4, vec![2, 7])].iter() {
+            for cell in &bref.rows[*r].cells {
+                if !keep.contains(&cell.get_pos().1) {
+                    cell.reduce(0b000000001).ok();
+                }
+            }
+        }
+        assert!(find_fish(bref, 2).is_empty());
+        let result = find_finned_fish(bref, 2);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].technique, "Finned X-Wing");
+        assert_eq!(result[0].eliminations, vec![(10, 0b000000001), (19, 0b000000001)]);
+    }
+    #[test]
+    fn test_sashimi_x_wing() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // in row 1 value 1 can only be placed in col 2 and 7, in row 5 only in col 3 and col 7
+        for (r, keep) in [(0, vec![2, 7]), (4, vec![3, 7])].iter() {
+            for cell in &bref.rows[*r].cells {
+                if !keep.contains(&cell.get_pos().1) {
+                    cell.reduce(0b000000001).ok();
+                }
+            }
+        }
+        // r2c3 and r3c3 can not be 1, so the fin r1c2 eliminates nothing
+        bref.rows[1].cells[2].reduce(0b000000001).ok();
+        bref.rows[2].cells[2].reduce(0b000000001).ok();
+        assert!(find_fish(bref, 2).is_empty());
+        let result = find_finned_fish(bref, 2);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].technique, "Sashimi X-Wing");
+        assert_eq!(result[0].units, vec![0, 4, 10, 15]);
+        assert_eq!(result[0].eliminations, vec![(28, 0b000000001), (46, 0b000000001)]);
+    }
+}
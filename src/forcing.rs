@@ -0,0 +1,271 @@
+use crate::sudoku_board::*;
+use crate::sudoku_state::*;
+use crate::deduction::*;
+use crate::technique::*;
+use crate::data::*;
+
+// the board techniques of the pipeline up to this difficulty (singles, pointing and claiming) follow an assumption
+const PROPAGATE_DIFFICULTY:f32 = 2.8;
+
+/**
+ * The consequences of placing one value in one cell, found on a copy of the board
+ * with the logic within the rows, cols and squares.
+ */
+pub struct Branch {
+    idx: usize,
+    value_mask: usize,
+    contradiction: bool,
+    states: Vec<CellState>,
+    path: Vec<usize>,       // the assumed cell followed by the cells solved because of it in the order they were solved, ending in the contradiction
+    units: Vec<usize>,      // the rows, cols and squares in which the cells of the path were solved or the contradiction was found
+}
+
+/**
+ * Nishio: place a value in a cell and follow the consequences.
+ * If this leads to a contradiction, the value can not be placed in the cell.
+ */
+pub fn find_nishio(_board:&SudokuBoard, branches:&[Branch]) -> Vec<Deduction> {
+    let mut result = vec![];
+    for branch in branches.iter().filter(|b| b.contradiction) {
+        let mut deduction = Deduction::new("Nishio");
+        deduction.eliminate(branch.idx, branch.value_mask);
+        deduction.reason_cells = branch.path.clone();
+        deduction.units = branch.units.clone();
+        result.push(deduction);
+    }
+    result
+}
+
+/**
+ * Cell forcing chain: place each of the values of a cell in turn. 
+ * What all of them (except the contradictions) have in common is true.
+ */
+pub fn find_cell_forcing_chain(board:&SudokuBoard, branches:&[Branch]) -> Vec<Deduction> {
+    let mut result = vec![];
+    for cell in board.all_cells() {
+        let cell_branches:Vec<&Branch> = branches.iter().filter(|b| b.idx == cell.get_idx()).collect();
+        let deduction = common_outcome("Cell Forcing Chain", board, &cell_branches);
+        if !deduction.is_empty() {
+            result.push(deduction);
+        }
+    }
+    result
+}
+
+/**
+ * Unit forcing chain: place a value in each of its possible cells within a row, col or square in turn. 
+ * What all of them (except the contradictions) have in common is true.
+ */
+pub fn find_unit_forcing_chain(board:&SudokuBoard, branches:&[Branch]) -> Vec<Deduction> {
+    let mut result:Vec<Deduction> = vec![];
+    for (n, unit) in board.all_logic_squares().iter().enumerate() {
+        for value in 0..CELL_SIZE {
+            let unit_branches:Vec<&Branch> = branches.iter()
+                .filter(|b| b.value_mask == 1 << value && unit.contains(board.get_cell(b.idx)))
+                .collect();
+            let mut deduction = common_outcome("Unit Forcing Chain", board, &unit_branches);
+            if !deduction.is_empty() && !result.iter().any(|d| d.placements == deduction.placements && d.eliminations == deduction.eliminations) {
+                deduction.units.retain(|u| *u != n);
+                deduction.units.insert(0, n);
+                result.push(deduction);
+            }
+        }
+    }
+    result
+}
+
+/**
+ * Follow the consequences of each possible value in each cell,
+ * with the techniques of the caller's pipeline and only the uniqueness techniques if the solution is unique
+ */
+pub fn assumptions(board:&SudokuBoard, pipeline:&Pipeline, unique:bool) -> Vec<Branch> {
+    let mut state = SudokuState::new();
+    state.set_pipeline(pipeline.clone());
+    state.set_unique(unique);
+    let copy = board.duplicate();
+    let original = board.snapshot();
+    let mut result = vec![];
+    for cell in board.all_cells() {
+        let idx = cell.get_idx();
+        for value in (0..CELL_SIZE).filter(|v| cell.get_candidates() & (1 << v) != 0) {
+            copy.push();
+            copy.get_cell(idx).set_solved_value(1 << value);
+            let mut branch = Branch { idx, value_mask: 1 << value, contradiction: false, states: vec![], path: vec![idx], units: vec![] };
+            branch.contradiction = propagate(&state, &copy, &original, &mut branch).is_err();
+            branch.states = copy.snapshot();
+            result.push(branch);
+            copy.pop();
+        }
+    }
+    result
+}
+
+/**
+ * Resolve the rows, cols and squares and apply the cheap board techniques until nothing changes anymore.
+ * Add each cell solved since the original state to the path of the branch, Err on a contradiction.
+ */
+fn propagate(state:&SudokuState, board:&SudokuBoard, original:&[CellState], branch:&mut Branch) -> Result<(), String> {
+    let techniques = state.get_pipeline().up_to(PROPAGATE_DIFFICULTY).techniques(state.is_unique());
+    loop {
+        let before = board.snapshot();
+        for (n, unit) in board.all_logic_squares().iter().enumerate() {
+            let resolved = state.resolve_square(board, n);
+            let mut solved_mask = 0;
+            for cell in unit.get_cells() {
+                let idx = cell.get_idx();
+                match cell.get_state() {
+                    CellState::Solved(mask, _) => {
+                        if original[idx] != cell.get_state() && !branch.path.contains(&idx) {
+                            branch.path.push(idx);
+                            add_unit(&mut branch.units, n);
+                        }
+                        if solved_mask & mask != 0 {
+                            add_unit(&mut branch.units, n);
+                            return Err(format!("Error: in {} value {} is placed twice", unit.get_id(), mask_digits(mask)));
+                        }
+                        solved_mask |= mask;
+                    },
+                    CellState::Error => {
+                        branch.path.push(idx);
+                        add_unit(&mut branch.units, n);
+                        return Err(format!("Error: no value left for {}", cell_name(idx)));
+                    },
+                    _ => (),
+                }
+            }
+            if resolved.is_err() {
+                add_unit(&mut branch.units, n);
+            }
+            resolved?;
+        }
+        for technique in &techniques {
+            for deduction in technique.find(board)? {
+                if let Err(error) = deduction.apply(board) {
+                    for unit in &deduction.units {
+                        add_unit(&mut branch.units, *unit);
+                    }
+                    return Err(error);
+                }
+            }
+        }
+        if board.snapshot() == before {
+            return Ok(());
+        }
+    }
+}
+
+fn add_unit(units:&mut Vec<usize>, unit:usize) {
+    if !units.contains(&unit) {
+        units.push(unit);
+    }
+}
+
+// the placements and eliminations which are the same in all branches
+fn common_outcome(name:&str, board:&SudokuBoard, branches:&[&Branch]) -> Deduction {
+    let mut deduction = Deduction::new(name);
+    let valid:Vec<&&Branch> = branches.iter().filter(|b| !b.contradiction).collect();
+    if branches.len() < 2 || valid.is_empty() {
+        return deduction;
+    }
+    for cell in board.all_cells() {
+        let idx = cell.get_idx();
+        let candidates = cell.get_candidates();
+        if candidates == 0 {
+            continue;
+        }
+        let masks:Vec<usize> = valid.iter()
+            .map(|b| match b.states[idx] {
+                CellState::Solved(mask, _) | CellState::UnSolved(mask) => mask,
+                CellState::Error => 0,
+            })
+            .collect();
+        let solved = valid.iter().all(|b| matches!(b.states[idx], CellState::Solved(_, _)));
+        if solved && masks.iter().all(|m| *m == masks[0]) {
+            deduction.place(idx, masks[0]);
+        } else {
+            let removed = masks.iter().fold(candidates, |acc, m| acc & !m);
+            if removed != 0 {
+                deduction.eliminate(idx, removed);
+            }
+        }
+    }
+    if !deduction.is_empty() {
+        for branch in valid {
+            for idx in &branch.path {
+                if !deduction.reason_cells.contains(idx) {
+                    deduction.reason_cells.push(*idx);
+                }
+            }
+            for unit in &branch.units {
+                add_unit(&mut deduction.units, *unit);
+            }
+        }
+    }
+    deduction
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+
+    #[test]
+    fn test_nishio() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // a solved board with r1c1 {49} and r1c2 {49} open, 9 is already placed in col 2
+        let solution = "946531827357826149182947536269415378513768492478392615694283751835179264721654983";
+        for (idx, c) in solution.chars().enumerate() {
+            if idx < 2 {
+                bref.get_cell(idx).reduce(0b011110111).ok();
+            } else {
+                bref.get_cell(idx).set_init_value(c.to_digit(10).unwrap() as usize);
+            }
+        }
+        let result = find_nishio(bref, &assumptions(bref, &Pipeline::standard(), false));
+        let nishio = result.iter().find(|d| d.eliminations == vec![(0, 0b000001000)]);
+        assert!(nishio.is_some());
+        assert_eq!(nishio.unwrap().reason_cells[0], 0);
+        // r1c1 can not be 4 because of the 4 in col 1
+        assert_eq!(nishio.unwrap().units, vec![0, 9]);
+        assert!(!result.iter().any(|d| d.eliminations == vec![(0, 0b100000000)]));
+    }
+    // rows 7-9 are solved, r1c1 is {12}, r1c5 {13} and r5c1 {23}
+    fn wing_board(bref:&SudokuBoard) {
+        for (idx, c) in "694283751835179264721654983".chars().enumerate() {
+            bref.get_cell(54 + idx).set_init_value(c.to_digit(10).unwrap() as usize);
+        }
+        let state = SudokuState::new();
+        for unit in bref.all_logic_squares() {
+            state.reduce_square(unit).ok();
+        }
+        keep(bref.get_cell(0), 0b011);
+        keep(bref.get_cell(4), 0b101);
+        keep(bref.get_cell(36), 0b110);
+    }
+    #[test]
+    fn test_cell_forcing_chain() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // r1c1 {12}: if 1, r1c5 {13} is 3; if 2, r5c1 {23} is 3. Either way r5c5 is not 3
+        wing_board(bref);
+        let result = find_cell_forcing_chain(bref, &assumptions(bref, &Pipeline::standard().up_to(PROPAGATE_DIFFICULTY), false));
+        assert_eq!(result[0].eliminations, vec![(40, 0b100)]);
+        assert_eq!(result[0].reason_cells, vec![0, 4, 36]);
+        assert_eq!(result[0].units, vec![0, 4]);
+    }
+    #[test]
+    fn test_unit_forcing_chain() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // in row 1 value 1 can only be placed in r1c1 and r1c5: either r1c5 or r5c1 is 3, so r5c5 is not 3
+        wing_board(bref);
+        only_at(&bref.rows[0], &[0, 4]);
+        let result = find_unit_forcing_chain(bref, &assumptions(bref, &Pipeline::standard().up_to(PROPAGATE_DIFFICULTY), false));
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].eliminations, vec![(40, 0b100)]);
+        assert_eq!(result[0].reason_cells, vec![0, 4, 36]);
+        assert_eq!(result[0].units, vec![0, 4]);
+    }
+}
@@ -2,5 +2,20 @@ pub mod controller;
 pub mod data;
 pub mod sudoku_board;
 pub mod sudoku_state;
-
-
+pub mod deduction;
+pub mod basic;
+pub mod fish;
+pub mod wings;
+pub mod single_digit;
+pub mod coloring;
+pub mod chains;
+pub mod aic;
+pub mod uniqueness;
+pub mod als;
+pub mod forcing;
+pub mod technique;
+pub mod dlx;
+pub mod rating;
+pub mod sat;
+#[cfg(test)]
+pub mod test_util;
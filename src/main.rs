@@ -105,6 +105,16 @@ fn ui_build_menuitems() -> impl Widget<AppState> {
         .on_click(|ctx, _data, _env|  ctx.submit_command(COMMAND_STEP.with( "".to_string()  ) ) )
     )
     .with_flex_spacer(1.0)
+    .with_child(Button::new("Hint")
+        .disabled_if(|data:&AppState, _| data.isHintDisabled())    
+        .on_click(|ctx, _data, _env|  ctx.submit_command(COMMAND_HINT.with( "".to_string()  ) ) )
+    )
+    .with_flex_spacer(1.0)
+    .with_child(Button::new("Rate")
+        .disabled_if(|data:&AppState, _| data.isRateDisabled())    
+        .on_click(|ctx, _data, _env|  ctx.submit_command(COMMAND_RATE.with( "".to_string()  ) ) )
+    )
+    .with_flex_spacer(1.0)
     .with_child (
         Button::new("Solve")
            .disabled_if(|data:&AppState, _| data.isSolveDisabled())    
@@ -197,7 +207,11 @@ impl Widget<AppState> for CellWidget {
     ) {
     }
 
-    fn update(&mut self, _ctx: &mut UpdateCtx, _old_data: &AppState, _data: &AppState, _env: &Env) {}
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &AppState, data: &AppState, _env: &Env) {
+        if old_data.hint != data.hint {
+            ctx.request_paint();
+        }
+    }
 
     fn layout(
         &mut self,
@@ -228,7 +242,7 @@ impl Widget<AppState> for CellWidget {
     // The paint method gets called last, after an event flow.
     // It goes event -> update -> layout -> paint, and each method can influence the next.
     // Basically, anything that changes the appearance of a widget causes a paint.
-    fn paint(&mut self, ctx: &mut PaintCtx, _data: &AppState, env: &Env) {
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &AppState, env: &Env) {
         // Clear the whole widget with the color of your choice
         // (ctx.size() returns the size of the layout rect we're painting in)
         // Note: ctx also has a `clear` method, but that clears the whole context,
@@ -241,6 +255,15 @@ impl Widget<AppState> for CellWidget {
 
         ctx.stroke(rounded, &env.get(druid::theme::PRIMARY_DARK), 1.0);        
         ctx.fill(rounded, &env.get(theme::BACKGROUND_LIGHT));
+        // the cells of a hint: the pattern and the cells which change
+        if let Some(hint) = &data.hint {
+            let idx = self.cell.get_idx();
+            if hint.placements.iter().chain(&hint.eliminations).any(|(i, _)| *i == idx) {
+                ctx.fill(rounded, &Color::rgb8(0x60, 0x30, 0x30));
+            } else if hint.reason_cells.contains(&idx) {
+                ctx.fill(rounded, &Color::rgb8(0x30, 0x50, 0x30));
+            }
+        }
         
         let mut tekst = "".into();
         let mut t_size = 24.0;
@@ -250,6 +273,7 @@ impl Widget<AppState> for CellWidget {
             CellState::Solved(v, actor)   => {
                     t_color = match actor { 
                         CellActor::StartValue => Color::rgb8(0xEE, 0xEE, 0xEE),
+                        CellActor::Guessed(_) => Color::rgb8(0xEE, 0xA0, 0x22),
                         _                     => Color::rgb8(0x22, 0x80, 0xEE),
                     };
                     tekst = format!("{}",v.trailing_zeros()+1 );
@@ -345,6 +369,22 @@ fn ui_build_statusline() -> impl Widget<AppState> {
         .lens(AppState::su_state)
     )
     .with_flex_spacer(10.0)
+    .with_child( Label::new(|data: &SudokuState, _env: &_| match data.get_solution_count() {
+            Some(count) => format!("{}", count),
+            None        => "".to_string(),
+        }) 
+        .with_text_size(16.0)
+        .lens(AppState::su_state)
+    )
+    .with_flex_spacer(10.0)
+    .with_child( Label::new(|data: &SudokuState, _env: &_| match data.get_rating() {
+            Some(rating) => format!("{}", rating),
+            None         => "not rated".to_string(),
+        }) 
+        .with_text_size(16.0)
+        .lens(AppState::su_state)
+    )
+    .with_flex_spacer(10.0)
     .with_child( Label::new(|data: &String, _env: &_| data.clone())
         .with_text_size(16.0)
         .lens(AppState::message)
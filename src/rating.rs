@@ -0,0 +1,98 @@
+use std::fmt;
+use crate::sudoku_board::*;
+use crate::sudoku_state::*;
+use crate::data::*;
+
+/**
+ * The difficulty of a puzzle in the style of the Sudoku Explainer rating:
+ * solve with the cheapest technique in each step, the rating is the difficulty of the hardest step
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rating {
+    pub rating: f32,        // the difficulty of the hardest technique used
+    pub hardest: String,    // the name of that technique
+    pub steps: usize,
+    pub solved: bool,       // false if the techniques are not enough, the rating is then at least this one
+}
+
+/**
+ * Rate the puzzle of the board, only its start values, with the techniques of the state. The board itself is not changed
+ */
+pub fn rate(state:&SudokuState, board:&SudokuBoard) -> Rating {
+    let copy = board.puzzle();
+    let mut rating = Rating { rating: 0.0, hardest: String::new(), steps: 0, solved: false };
+    if copy.all_logic_squares().iter().any(|square| state.reduce_square(*square).is_err()) {
+        return rating;
+    }
+    let techniques = state.get_pipeline().techniques(state.is_unique());
+    'steps: loop {
+        if copy.count_solved().1 == CELL_COUNT {
+            rating.solved = copy.is_consistent();
+            break;
+        }
+        for technique in &techniques {
+            match technique.find_with(&copy, state.get_pipeline(), state.is_unique()).and_then(|deductions| state.apply_deductions(&copy, deductions)) {
+                Ok(applied) if applied.is_empty() => (),
+                Ok(_) => {
+                    rating.steps += 1;
+                    if technique.difficulty() > rating.rating {
+                        rating.rating = technique.difficulty();
+                        rating.hardest = technique.name().to_string();
+                    }
+                    continue 'steps;
+                },
+                Err(_) => break 'steps,
+            }
+        }
+        // stuck: none of the techniques changes the board
+        break;
+    }
+    rating
+}
+
+impl fmt::Display for Rating {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.solved && self.steps == 0 {
+            write!(f, "rating {:.1}", self.rating)
+        } else if self.solved {
+            write!(f, "rating {:.1} ({})", self.rating, self.hardest)
+        } else {
+            write!(f, "rating {:.1}+ (not solved)", self.rating)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate() {
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // a solved board with the first row open: each value has one place left in its col
+        let solution = "946531827357826149182947536269415378513768492478392615694283751835179264721654983";
+        for (idx, c) in solution.chars().enumerate().skip(CELL_SIZE) {
+            bref.get_cell(idx).set_init_value(c.to_digit(10).unwrap() as usize);
+        }
+        let state = SudokuState::new();
+        let before = bref.snapshot();
+        let rating = rate(&state, bref);
+        assert!(bref.snapshot() == before);
+        assert!(rating.solved);
+        assert_eq!(rating.hardest, "Hidden Single");
+        assert_eq!(rating.rating, 1.5);
+        assert_eq!(rating.steps, 1);
+        assert_eq!(format!("{}", rating), "rating 1.5 (Hidden Single)");
+        let mut state = state;
+        assert_eq!(state.check_rating(bref), None);
+        state.check_solutions(bref);
+        assert_eq!(state.check_rating(bref), Some(rating.clone()));
+        // after a step or solve the puzzle is still rated from its start values
+        state.resolve_step(bref);
+        assert_eq!(state.get_curr_count(), CELL_COUNT);
+        assert_eq!(rate(&state, bref), rating);
+        assert!(state.solve(bref) == GameState::Solved);
+        assert_eq!(state.check_rating(bref), Some(rating));
+    }
+}
@@ -0,0 +1,300 @@
+use std::fs;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::sudoku_board::*;
+use crate::deduction::*;
+use crate::data::*;
+use crate::dlx::*;
+
+/**
+ * The variable for a value in a cell: 1 ..= 729.
+ * The value is the bit position, so 0 is the digit 1.
+ */
+pub fn variable(idx:usize, value:usize) -> i32 {
+    (idx * CELL_SIZE + value + 1) as i32
+}
+
+/**
+ * Encode the board as a CNF formula in the DIMACS format.
+ * A solved cell gets its value, an unsolved cell one of its possible values.
+ * Each solution in blocked is excluded with a clause, to look for another solution.
+ */
+pub fn to_dimacs(board:&SudokuBoard, blocked:&[Solution]) -> String {
+    let mut clauses:Vec<Vec<i32>> = vec![];
+    for cell in board.all_cells() {
+        let idx = cell.get_idx();
+        let mask = cell.get_unresolved_mask();
+        // at least one of the possible values, none of the others
+        clauses.push((0..CELL_SIZE).filter(|v| mask & (1 << v) != 0).map(|v| variable(idx, v)).collect());
+        for value in (0..CELL_SIZE).filter(|v| mask & (1 << v) == 0) {
+            clauses.push(vec![-variable(idx, value)]);
+        }
+        // at most one value
+        for a in 0..CELL_SIZE {
+            for b in a + 1..CELL_SIZE {
+                clauses.push(vec![-variable(idx, a), -variable(idx, b)]);
+            }
+        }
+    }
+    for unit in board.all_logic_squares() {
+        let cells:Vec<usize> = unit.get_cells().iter().map(|c| c.get_idx()).collect();
+        for value in 0..CELL_SIZE {
+            // each value at least once and at most once
+            clauses.push(cells.iter().map(|idx| variable(*idx, value)).collect());
+            for a in 0..cells.len() {
+                for b in a + 1..cells.len() {
+                    clauses.push(vec![-variable(cells[a], value), -variable(cells[b], value)]);
+                }
+            }
+        }
+    }
+    for solution in blocked {
+        clauses.push((0..CELL_COUNT).map(|idx| -variable(idx, solution[idx].trailing_zeros() as usize)).collect());
+    }
+    let mut dimacs = format!("c sudoku3x3 {} blocked solutions\np cnf {} {}\n", blocked.len(), CELL_COUNT * CELL_SIZE, clauses.len());
+    for clause in clauses {
+        let literals:Vec<String> = clause.iter().map(|l| l.to_string()).collect();
+        dimacs += &format!("{} 0\n", literals.join(" "));
+    }
+    dimacs
+}
+
+/**
+ * Decode the output of a SAT solver: the competition format with an "s SATISFIABLE" line and "v" lines,
+ * or the minisat result file with "SAT" and the literals on the next line.
+ * Return None if the formula is unsatisfiable.
+ */
+pub fn read_model(text:&str) -> Result<Option<Solution>, String> {
+    let mut literals:Vec<i32> = vec![];
+    let mut satisfiable = None;
+    for line in text.lines() {
+        let line = line.trim();
+        let (status, values) = match line.split_whitespace().next() {
+            Some("s")       => (line[1..].trim(), ""),
+            Some("v")       => ("", &line[1..]),
+            Some("c") | None => continue,
+            Some(_)         => if line.chars().next().unwrap().is_alphabetic() { (line, "") } else { ("", line) },
+        };
+        match status {
+            "SATISFIABLE" | "SAT"     => satisfiable = Some(true),
+            "UNSATISFIABLE" | "UNSAT" => satisfiable = Some(false),
+            ""                        => (),
+            other                     => return Err(format!("Error: unknown solver status {}", other)),
+        }
+        for value in values.split_whitespace() {
+            literals.push(value.parse().map_err(|_| format!("Error: {} is not a literal", value))?);
+        }
+    }
+    match satisfiable {
+        None        => return Err("Error: no solver status found".to_string()),
+        Some(false) => return Ok(None),
+        Some(true)  => (),
+    }
+    let mut solution = vec![0; CELL_COUNT];
+    for literal in literals.into_iter().filter(|l| *l > 0 && *l as usize <= CELL_COUNT * CELL_SIZE) {
+        let var = literal as usize - 1;
+        if solution[var / CELL_SIZE] != 0 {
+            return Err(format!("Error: more than one value for cell {:?}", (var / CELL_SIZE / CELL_SIZE + 1, var / CELL_SIZE % CELL_SIZE + 1)));
+        }
+        solution[var / CELL_SIZE] = 1 << (var % CELL_SIZE);
+    }
+    if solution.contains(&0) {
+        return Err("Error: the model does not give a value for every cell".to_string());
+    }
+    Ok(Some(solution))
+}
+
+/**
+ * Write a model onto the board: each unsolved cell is solved with its value of the solution.
+ * Err if the solution does not fit the board, the cells which are already written stay solved.
+ */
+pub fn write_solution(board:&SudokuBoard, solution:&Solution) -> Result<(), String> {
+    for cell in board.all_cells() {
+        let idx = cell.get_idx();
+        let fits = match cell.get_state() {
+            CellState::Solved(mask, _) => mask == solution[idx],
+            _                          => cell.set_solved_value(solution[idx]),
+        };
+        if !fits {
+            return Err(format!("Error: the solution does not fit {}", cell_name(idx)));
+        }
+    }
+    Ok(())
+}
+
+// numbers the temporary files, so solvers can run at the same time
+static RUN_COUNT:AtomicUsize = AtomicUsize::new(0);
+
+/**
+ * Run an external SAT solver on the board.
+ * In the arguments {cnf} is replaced by the formula file and {model} by the result file, e.g. 
+ * minisat {cnf} {model} or kissat {cnf}. Without {model} the model is read from the output of the solver.
+ */
+pub fn run_solver(program:&str, args:&[&str], board:&SudokuBoard, blocked:&[Solution]) -> Result<Option<Solution>, String> {
+    let run = RUN_COUNT.fetch_add(1, Ordering::SeqCst);
+    let base = std::env::temp_dir().join(format!("sudoku3x3-{}-{}", std::process::id(), run));
+    let cnf = base.with_extension("cnf");
+    let model = base.with_extension("model");
+    fs::write(&cnf, to_dimacs(board, blocked)).map_err(|e| format!("Error: can not write {:?}: {}", cnf, e))?;
+    let _ = fs::remove_file(&model);
+    let args:Vec<String> = args.iter()
+        .map(|a| a.replace("{cnf}", &cnf.to_string_lossy()).replace("{model}", &model.to_string_lossy()))
+        .collect();
+    let output = Command::new(program).args(&args).output();
+    let model_text = fs::read_to_string(&model);
+    let _ = fs::remove_file(&cnf);
+    let _ = fs::remove_file(&model);
+    let output = output.map_err(|e| format!("Error: can not run {}: {}", program, e))?;
+    let text = match model_text {
+        Ok(text) => text,
+        Err(_)   => String::from_utf8_lossy(&output.stdout).to_string(),
+    };
+    // solvers use the exit code for the result: 10 satisfiable, 20 unsatisfiable (or 0), anything else is a failure
+    match output.status.code() {
+        Some(0) | Some(10) | Some(20) => read_model(&text),
+        _ => Err(format!("Error: {} failed ({})", program, output.status)),
+    }
+}
+
+/**
+ * Solve the board with an external SAT solver and write the model onto the board.
+ * Return false if the board has no solution.
+ */
+pub fn sat_solve(program:&str, args:&[&str], board:&SudokuBoard) -> Result<bool, String> {
+    match run_solver(program, args, board, &[])? {
+        None           => Ok(false),
+        Some(solution) => write_solution(board, &solution).map(|_| true),
+    }
+}
+
+/**
+ * Prove with an external SAT solver that the board has no, one or more solutions:
+ * solve, then block the solution and solve again.
+ */
+pub fn sat_count_solutions(program:&str, args:&[&str], board:&SudokuBoard) -> Result<SolutionCount, String> {
+    match run_solver(program, args, board, &[])? {
+        None           => Ok(SolutionCount::NoSolution),
+        Some(solution) => match run_solver(program, args, board, &[solution])? {
+            None    => Ok(SolutionCount::Unique),
+            Some(_) => Ok(SolutionCount::AtLeast(2)),
+        },
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // true if the model, a solution, satisfies all clauses of the formula
+    fn satisfies(dimacs:&str, solution:&Solution) -> bool {
+        dimacs.lines().filter(|l| !l.starts_with('c') && !l.starts_with('p')).all(|line| {
+            line.split_whitespace().map(|l| l.parse::<i32>().unwrap()).filter(|l| *l != 0).any(|l| {
+                let var = l.unsigned_abs() as usize - 1;
+                let set = solution[var / CELL_SIZE] == 1 << (var % CELL_SIZE);
+                set == (l > 0)
+            })
+        })
+    }
+    // the literals of the values of the solution, as a minisat model line
+    fn model_line(solution:&Solution) -> String {
+        let literals:Vec<String> = (0..CELL_COUNT)
+            .map(|idx| variable(idx, solution[idx].trailing_zeros() as usize).to_string())
+            .collect();
+        format!("{} 0", literals.join(" "))
+    }
+
+    #[test]
+    fn test_dimacs() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        bref.init();
+        let solutions = dlx_solutions(bref, 2);
+        let dimacs = to_dimacs(bref, &[]);
+        assert!(dimacs.lines().nth(1).unwrap().starts_with("p cnf 729 "));
+        assert!(satisfies(&dimacs, &solutions[0]));
+        assert!(satisfies(&dimacs, &solutions[1]));
+        let blocked = to_dimacs(bref, &solutions[0..1]);
+        assert!(!satisfies(&blocked, &solutions[0]));
+        assert!(satisfies(&blocked, &solutions[1]));
+        // r1c1 can not have its value of the first solution anymore
+        bref.get_cell(0).reduce(solutions[0][0]).ok();
+        assert!(!satisfies(&to_dimacs(bref, &[]), &solutions[0]));
+    }
+    #[test]
+    fn test_read_model() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        bref.init();
+        let solution = &dlx_solutions(bref, 1)[0];
+        let literals:Vec<String> = (0..CELL_COUNT * CELL_SIZE)
+            .map(|var| if solution[var / CELL_SIZE] == 1 << (var % CELL_SIZE) { (var + 1) as i32 } else { -(var as i32 + 1) })
+            .map(|l| l.to_string())
+            .collect();
+        let competition = format!("c solver output\ns SATISFIABLE\nv {}\nv {} 0\n", literals[..300].join(" "), literals[300..].join(" "));
+        assert_eq!(read_model(&competition), Ok(Some(solution.clone())));
+        let minisat = format!("SAT\n{} 0\n", literals.join(" "));
+        assert_eq!(read_model(&minisat), Ok(Some(solution.clone())));
+        assert_eq!(read_model("UNSAT\n"), Ok(None));
+        assert_eq!(read_model("s UNSATISFIABLE\n"), Ok(None));
+        assert!(read_model("SAT\n1 2 0\n").is_err());
+    }
+    #[test]
+    #[cfg(unix)]
+    fn test_run_solver() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        bref.init();
+        let solution = &dlx_solutions(bref, 1)[0];
+        // a stub solver which checks the formula file and writes the model file
+        let script = format!("grep -q '^p cnf 729 ' \"$0\" || exit 1; printf 'SAT\\n{}\\n' > \"$1\"; exit 10", model_line(solution));
+        assert_eq!(run_solver("sh", &["-c", &script, "{cnf}", "{model}"], bref, &[]), Ok(Some(solution.clone())));
+        // without a model file the output is read
+        assert_eq!(run_solver("sh", &["-c", "echo 's UNSATISFIABLE'; exit 20"], bref, &[]), Ok(None));
+        assert!(run_solver("sh", &["-c", "echo 's SATISFIABLE'; exit 3"], bref, &[]).is_err());
+        assert!(run_solver("sudoku3x3-no-such-solver", &[], bref, &[]).is_err());
+        // the temporary files are removed
+        let prefix = format!("sudoku3x3-{}-", std::process::id());
+        assert!(!fs::read_dir(std::env::temp_dir()).unwrap()
+            .any(|entry| entry.unwrap().file_name().to_string_lossy().starts_with(&prefix)));
+    }
+    #[test]
+    fn test_write_solution() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        bref.init();
+        let solutions = dlx_solutions(bref, 2);
+        bref.push();
+        assert!(write_solution(bref, &solutions[0]).is_ok());
+        assert_eq!(bref.count_solved().1, CELL_COUNT);
+        assert!(bref.is_consistent());
+        assert_eq!(bref.snapshot().iter().map(|s| match s {
+            CellState::Solved(mask, _) => *mask,
+            _                          => 0,
+        }).collect::<Solution>(), solutions[0]);
+        // the other solution does not fit anymore
+        assert!(write_solution(bref, &solutions[1]).is_err());
+        bref.pop();
+    }
+    #[test]
+    #[cfg(unix)]
+    fn test_sat_count_solutions() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        bref.init();
+        let solutions = dlx_solutions(bref, 2);
+        // a stub solver which finds the first solution, and the second one once the first is blocked
+        let script = format!("if grep -q '^c sudoku3x3 0 blocked' \"$0\"; then printf 'SAT\\n{}\\n' > \"$1\"; \
+                              else printf 'SAT\\n{}\\n' > \"$1\"; fi; exit 10", model_line(&solutions[0]), model_line(&solutions[1]));
+        assert_eq!(sat_count_solutions("sh", &["-c", &script, "{cnf}", "{model}"], bref), Ok(SolutionCount::AtLeast(2)));
+        // a stub solver for a unique puzzle: once the solution is blocked the formula is unsatisfiable
+        let script = format!("if grep -q '^c sudoku3x3 0 blocked' \"$0\"; then printf 'SAT\\n{}\\n' > \"$1\"; exit 10; \
+                              else echo 's UNSATISFIABLE'; exit 20; fi", model_line(&solutions[0]));
+        assert_eq!(sat_count_solutions("sh", &["-c", &script, "{cnf}", "{model}"], bref), Ok(SolutionCount::Unique));
+        assert_eq!(sat_count_solutions("sh", &["-c", "echo 's UNSATISFIABLE'; exit 20"], bref), Ok(SolutionCount::NoSolution));
+        // the model is written onto the board
+        assert_eq!(sat_solve("sh", &["-c", &script, "{cnf}", "{model}"], bref), Ok(true));
+        assert_eq!(bref.count_solved().1, CELL_COUNT);
+        assert!(bref.is_consistent());
+    }
+}
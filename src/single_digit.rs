@@ -0,0 +1,205 @@
+use crate::sudoku_board::*;
+use crate::sudoku_state::*;
+use crate::deduction::*;
+use crate::data::*;
+
+/**
+ * Skyscraper: two rows (or cols) in which a value can only be placed in two cells, with one end of both
+ * strong links in the same col (or row). One of the other ends (the tips) will contain the value,
+ * so the value can not be placed in a cell which sees both tips.
+ */
+pub fn find_skyscraper(board:&SudokuBoard) -> Vec<Deduction> {
+    let mut result = vec![];
+    for value in 0..CELL_SIZE {
+        let value_mask = 1 << value;
+        for by_row in [true, false].iter() {
+            let lines:Vec<&dyn RowColSquare> = if *by_row { 
+                board.rows.iter().map(|r| r as &dyn RowColSquare).collect()
+            } else {
+                board.cols.iter().map(|c| c as &dyn RowColSquare).collect()
+            };
+            // the position of a cell across the lines: the col for rows, the row for cols
+            let across = |cell:&RcSudokuCell| if *by_row { cell.get_col() } else { cell.get_row() };
+            let links:Vec<(RcSudokuCell, RcSudokuCell)> = lines.iter().filter_map(|line| strong_link(*line, value_mask)).collect();
+            for pair in combinations(&(0..links.len()).collect::<Vec<usize>>(), 2) {
+                let (first, second) = (&links[pair[0]], &links[pair[1]]);
+                for (base1, tip1) in [(&first.0, &first.1), (&first.1, &first.0)].iter() {
+                    for (base2, tip2) in [(&second.0, &second.1), (&second.1, &second.0)].iter() {
+                        if across(base1) != across(base2) || across(tip1) == across(tip2) {
+                            continue;
+                        }
+                        let deduction = tips_deduction("Skyscraper", board, &[tip1, base1, base2, tip2], tip1, tip2, value_mask);
+                        if !deduction.is_empty() {
+                            result.push(deduction);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    result
+}
+
+/**
+ * 2-String Kite: a strong link in a row and a strong link in a col, with one end of both links in the same square.
+ * One of the other ends (the tips) will contain the value, so the value can not be placed in a cell which sees both tips.
+ */
+pub fn find_two_string_kite(board:&SudokuBoard) -> Vec<Deduction> {
+    let mut result = vec![];
+    for value in 0..CELL_SIZE {
+        let value_mask = 1 << value;
+        let row_links:Vec<(RcSudokuCell, RcSudokuCell)> = board.rows.iter().filter_map(|r| strong_link(r, value_mask)).collect();
+        let col_links:Vec<(RcSudokuCell, RcSudokuCell)> = board.cols.iter().filter_map(|c| strong_link(c, value_mask)).collect();
+        for row_link in &row_links {
+            for col_link in &col_links {
+                for (row_base, row_tip) in [(&row_link.0, &row_link.1), (&row_link.1, &row_link.0)].iter() {
+                    for (col_base, col_tip) in [(&col_link.0, &col_link.1), (&col_link.1, &col_link.0)].iter() {
+                        let square = board.square_of(row_base);
+                        if row_base.get_idx() == col_base.get_idx() || !square.contains(col_base)
+                            || square.contains(row_tip) || square.contains(col_tip) {
+                            continue;
+                        }
+                        let deduction = tips_deduction("2-String Kite", board, &[row_tip, row_base, col_base, col_tip], row_tip, col_tip, value_mask);
+                        if !deduction.is_empty() {
+                            result.push(deduction);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    result
+}
+
+/**
+ * Empty Rectangle: within a square a value can only be placed in one row and one col of that square (the cross).
+ * A strong link in a col (or row) outside the square, with one end in the row (or col) of the cross, 
+ * forbids the value in the cell where the other end meets the col (or row) of the cross.
+ */
+pub fn find_empty_rectangle(board:&SudokuBoard) -> Vec<Deduction> {
+    let mut result = vec![];
+    for (s, square) in board.squares.iter().enumerate() {
+        let (square_row, square_col) = ((s / 3) * 3, (s % 3) * 3);
+        let in_square_rows = |r:usize| r >= square_row && r < square_row + 3;
+        let in_square_cols = |c:usize| c >= square_col && c < square_col + 3;
+        for value in 0..CELL_SIZE {
+            let value_mask = 1 << value;
+            let value_cells = square.get_value_cells(value_mask);
+            if value_cells.len() < 2 || value_cells.iter().all(|c| c.get_row() == value_cells[0].get_row())
+                || value_cells.iter().all(|c| c.get_col() == value_cells[0].get_col()) {
+                continue;
+            }
+            for cross_row in square_row..square_row + 3 {
+                for cross_col in square_col..square_col + 3 {
+                    if !value_cells.iter().all(|c| c.get_row() == cross_row || c.get_col() == cross_col) {
+                        continue;
+                    }
+                    let mut links = vec![];
+                    for c in (0..CELL_SIZE).filter(|c| !in_square_cols(*c)) {
+                        if let Some(link) = strong_link(&board.cols[c], value_mask) {
+                            for (near, far) in [(&link.0, &link.1), (&link.1, &link.0)].iter() {
+                                if near.get_row() == cross_row && !in_square_rows(far.get_row()) {
+                                    links.push((RcSudokuCell::clone(near), RcSudokuCell::clone(far), &board.rows[far.get_row()].cells[cross_col], CELL_SIZE + c));
+                                }
+                            }
+                        }
+                    }
+                    for r in (0..CELL_SIZE).filter(|r| !in_square_rows(*r)) {
+                        if let Some(link) = strong_link(&board.rows[r], value_mask) {
+                            for (near, far) in [(&link.0, &link.1), (&link.1, &link.0)].iter() {
+                                if near.get_col() == cross_col && !in_square_cols(far.get_col()) {
+                                    links.push((RcSudokuCell::clone(near), RcSudokuCell::clone(far), &board.rows[cross_row].cells[far.get_col()], r));
+                                }
+                            }
+                        }
+                    }
+                    for (near, far, target, unit) in links {
+                        if target.get_candidates() & value_mask == 0 {
+                            continue;
+                        }
+                        let mut deduction = Deduction::new("Empty Rectangle");
+                        deduction.units = vec![2 * CELL_SIZE + s, unit];
+                        for cell in &value_cells {
+                            deduction.reason_cells.push(cell.get_idx());
+                        }
+                        deduction.reason_cells.push(near.get_idx());
+                        deduction.reason_cells.push(far.get_idx());
+                        deduction.eliminate(target.get_idx(), value_mask);
+                        if !result.contains(&deduction) {
+                            result.push(deduction);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    result
+}
+
+// the two cells of a row, col or square in which the value can be placed, if there are exactly two
+pub fn strong_link(unit:&dyn RowColSquare, value_mask:usize) -> Option<(RcSudokuCell, RcSudokuCell)> {
+    let cells = unit.get_value_cells(value_mask);
+    if cells.len() == 2 {
+        Some((cells[0].clone(), cells[1].clone()))
+    } else {
+        None
+    }
+}
+
+// remove the value from all cells which see both tips
+fn tips_deduction(name:&str, board:&SudokuBoard, links:&[&RcSudokuCell], tip1:&RcSudokuCell, tip2:&RcSudokuCell, value_mask:usize) -> Deduction {
+    let mut deduction = Deduction::new(name);
+    for cell in links {
+        deduction.reason_cells.push(cell.get_idx());
+    }
+    for pair in deduction.reason_cells.clone().windows(2) {
+        deduction.add_units(&common_units(pair));
+    }
+    for cell in board.common_peers(&[tip1, tip2]) {
+        if cell.get_candidates() & value_mask != 0 && !links.iter().any(|l| l.get_idx() == cell.get_idx()) {
+            deduction.eliminate(cell.get_idx(), value_mask);
+        }
+    }
+    deduction
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+
+    #[test]
+    fn test_skyscraper() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        only_at(&bref.rows[0], &[0, 4]);
+        only_at(&bref.rows[2], &[0, 3]);
+        let result = find_skyscraper(bref);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].reason_cells, vec![4, 0, 18, 21]);
+        assert_eq!(result[0].units, vec![0, 9, 18, 2]);
+        assert_eq!(result[0].eliminations, vec![(12, 0b001), (13, 0b001), (14, 0b001)]);
+    }
+    #[test]
+    fn test_two_string_kite() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        only_at(&bref.rows[0], &[1, 5]);
+        only_at(&bref.cols[0], &[2, 7]);
+        assert!(find_skyscraper(bref).is_empty());
+        let result = find_two_string_kite(bref);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].eliminations, vec![(68, 0b001)]);
+    }
+    #[test]
+    fn test_empty_rectangle() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        only_at(&bref.squares[4], &[1, 3, 4, 5, 7]);
+        only_at(&bref.cols[7], &[0, 4]);
+        let result = find_empty_rectangle(bref);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].eliminations, vec![(4, 0b001)]);
+    }
+}
@@ -98,14 +98,8 @@ impl SudokuCell {
                     let new_mask = my_mask & !other_mask;
                     self.value = CellState::UnSolved( new_mask);
 
-                    let nr_bits = new_mask.count_ones(); 
-                    if nr_bits == 1 {
-                        println!("After reduce only one bit left!  {:?}  my mask {:09b}  other {:09b}",  self.get_pos(),  my_mask, other_mask  );
-                    } else  if my_mask != new_mask {
-                    };
-                    if nr_bits == 0 {
+                    if new_mask.count_ones() == 0 {
                         let message = format!("Error: in reduce zero bits left for {:?}  incoming mask {:09b}", self.get_pos(), other_mask);
-                        println!("{}", message);
                         self.value = CellState::Error;
                         Err( message )
                     }else {
@@ -117,6 +111,29 @@ impl SudokuCell {
         }        
     }
 
+    // If this cell is NOT resolved remove the bits in the mask from the possible values.
+    // Contrary to reduce this is an explicit elimination: removing the last possible value is an error
+    // Return true if at least one possible value is removed
+    fn eliminate(&mut self, mask:usize) -> Result<bool, String> {
+        match self.value {
+            CellState::UnSolved(my_mask) => {
+                let new_mask = my_mask & !mask;
+                if new_mask == my_mask {
+                    return Ok(false);
+                }
+                if new_mask == 0 {
+                    let message = format!("Error: in eliminate zero bits left for {:?}  mask {:09b}", self.get_pos(), mask);
+                    self.value = CellState::Error;
+                    return Err(message);
+                }
+                self.value = CellState::UnSolved(new_mask);
+                Ok(true)
+            },
+            CellState::Solved(_,_) => Ok(false),
+            CellState::Error => Err("Cell in Error state".to_string())
+        }
+    }
+
     // If this cell is NOT resolved , and has only one possible value left
     // change the state from Unsolved to Solved and return true
     // In all other cases do nothing and return false.
@@ -126,7 +143,6 @@ impl SudokuCell {
                     let nr_bits = my_mask.count_ones(); 
                     if nr_bits == 1 {
                         self.value = CellState::Solved( my_mask, CellActor::Resolved);  
-                        println!("Resolved in reduce cell {:?} value {} ",  self.get_pos(), self.as_string()  );
                         true  
                     } else {
                         false 
@@ -152,7 +168,6 @@ impl SudokuCell {
             CellState::UnSolved(n)  => {
                 if (n & mask)  == mask {
                     self.value = CellState::Solved(mask, CellActor::Resolved);
-                    println!("Resolved cell {:?} value {}", self.get_pos(), self.as_string());
                     return true;
                 }
                 false
@@ -160,6 +175,19 @@ impl SudokuCell {
             _ => false,
         }
     } 
+    /**
+     * Set a guessed value, but only if it is one of the possible values
+     * depth is the number of guesses this one depends on, including itself
+     */
+    fn set_guessed_value(&mut self, mask:usize, depth:usize) -> bool  {
+        match self.value {
+            CellState::UnSolved(n) if n & mask == mask => {
+                self.value = CellState::Solved(mask, CellActor::Guessed(depth));
+                true
+            }
+            _ => false,
+        }
+    } 
     // return (0,0) if cell is not resolved
     // return (1,1) if cell is resolved, but in initial state
     // return (0,1) if the cell is resolved due to stepping
@@ -229,11 +257,26 @@ impl RcSudokuCell {
     pub fn get_unresolved_mask(&self) -> usize {
         self.cell.borrow().get_unresolved_mask()
     }
+    /**
+     *  The possible values of an unresolved cell, 0 for a resolved cell
+     */
+    pub fn get_candidates(&self) -> usize {
+        match self.cell.borrow().value {
+            CellState::UnSolved(n) => n,
+            _                      => 0,
+        }
+    }
+    pub fn eliminate(&self, mask:usize) -> Result<bool, String> {
+        self.cell.borrow_mut().eliminate(mask)
+    }
     pub fn set_solved_value(&self, mask:usize ) -> bool  {
         // dereference the Rc into the CellState
         self.cell.borrow_mut().set_solved_value(mask)
     } 
 
+    pub fn set_guessed_value(&self, mask:usize, depth:usize) -> bool  {
+        self.cell.borrow_mut().set_guessed_value(mask, depth)
+    } 
     pub fn count_solved(&self) -> (usize,usize) {
         self.cell.borrow().count_solved()
     }
@@ -250,6 +293,26 @@ impl RcSudokuCell {
     pub fn get_pos(&self) -> (usize,usize) {
         self.cell.borrow().get_pos() 
     }
+    // index of the cell in the board, 0 .. CELL_COUNT
+    pub fn get_idx(&self) -> usize {
+        self.cell.borrow().idx
+    }
+    // index of the row and col of the cell, 0 .. CELL_SIZE
+    pub fn get_row(&self) -> usize {
+        self.get_idx() / CELL_SIZE
+    }
+    pub fn get_col(&self) -> usize {
+        self.get_idx() % CELL_SIZE
+    }
+    // true if the other cell is in the same row, col or square (a cell does not see itself)
+    pub fn sees(&self, other:&RcSudokuCell) -> bool {
+        let (r1, c1) = self.get_pos();
+        let (r2, c2) = other.get_pos();
+        if r1 == r2 && c1 == c2 {
+            return false;
+        }
+        r1 == r2 || c1 == c2 || ( (r1 - 1) / 3 == (r2 - 1) / 3 && (c1 - 1) / 3 == (c2 - 1) / 3 )
+    }
     pub fn as_string(&self) -> String {
         self.cell.borrow().as_string() 
     }
@@ -296,6 +359,19 @@ impl AllCells {
 pub trait RowColSquare  {
     fn get_cells(&self) -> &Vec<RcSudokuCell>;
     fn get_id(&self) -> &String;
+
+    // true if the cell is part of this row, col or square
+    fn contains(&self, cell:&RcSudokuCell) -> bool {
+        let idx = cell.get_idx();
+        self.get_cells().iter().any(|c| c.get_idx() == idx)
+    }
+    // the unresolved cells in which the value (as bitmask) can still be placed
+    fn get_value_cells(&self, value_mask:usize) -> Vec<RcSudokuCell> {
+        self.get_cells().iter()
+            .filter(|c| c.get_candidates() & value_mask == value_mask)
+            .cloned()
+            .collect()
+    }
 }
 
 #[derive(Clone, Data, Lens )]
@@ -387,6 +463,26 @@ impl Square {
     }
 }
 
+// the index of the square of a cell, squares are numbered row by row
+pub fn square_index(idx:usize) -> usize {
+    (idx / CELL_SIZE / 3) * 3 + idx % CELL_SIZE / 3
+}
+
+// the row, col and square of a cell, as index in all_logic_squares
+pub fn cell_units(idx:usize) -> [usize;3] {
+    [idx / CELL_SIZE, CELL_SIZE + idx % CELL_SIZE, 2 * CELL_SIZE + square_index(idx)]
+}
+
+// the rows, cols and squares which contain all given cells, as index in all_logic_squares
+pub fn common_units(cells:&[usize]) -> Vec<usize> {
+    match cells.split_first() {
+        Some((first, rest)) => cell_units(*first).iter()
+                .filter(|unit| rest.iter().all(|idx| cell_units(*idx).contains(unit)))
+                .cloned()
+                .collect(),
+        None => vec![],
+    }
+}
 
 #[derive(Clone,  Lens )]
 pub struct SudokuBoard{
@@ -394,13 +490,17 @@ pub struct SudokuBoard{
     pub rows: Vec<Row>,
     pub cols: Vec<Col>,
     pub squares: Vec<Square>,
+    peers: Vec<Vec<usize>>,     // per cell index the indexes of all cells in the same row, col or square
 }
 
 
 impl SudokuBoard {
     pub fn new () -> SudokuBoard {
         println!("New board. Board size: {}:{}", CELL_ROW ,CELL_COL);
-
+        SudokuBoard::empty()
+    }
+    // a board without any message, for the copies of the techniques
+    fn empty () -> SudokuBoard {
         SudokuBoard {
             allcells:AllCells::new(),
             rows: { let mut  rws  = vec![];
@@ -421,6 +521,7 @@ impl SudokuBoard {
                     };
                     sq   
                   },
+            peers: vec![],
         }        
     }
     pub fn reset(&self) {         self.allcells.reset();    }
@@ -444,6 +545,12 @@ impl SudokuBoard {
         self.squares[6].wire(6,0, &self.allcells);
         self.squares[7].wire(6,3, &self.allcells);
         self.squares[8].wire(6,6, &self.allcells);
+        self.peers = self.allcells.cells.iter()
+            .map(|cell| self.allcells.cells.iter()
+                .filter(|other| cell.sees(other))
+                .map(|other| other.get_idx())
+                .collect())
+            .collect();
         self
     }
 
@@ -460,6 +567,89 @@ impl SudokuBoard {
         }
         result
     }
+    pub fn all_lines(&self) -> Vec<&dyn RowColSquare> {
+        let mut result:Vec<&dyn RowColSquare> = vec![];
+        for row in &self.rows {
+            result.push(row);
+        }
+        for col in &self.cols {
+            result.push(col);
+        }
+        result
+    }
+    pub fn get_cell(&self, idx:usize) -> &RcSudokuCell {
+        &self.allcells.cells[idx]
+    }
+    pub fn all_cells(&self) -> &Vec<RcSudokuCell> {
+        &self.allcells.cells
+    }
+    // all cells which see the given cell
+    pub fn get_peers(&self, cell:&RcSudokuCell) -> Vec<RcSudokuCell> {
+        self.peers[cell.get_idx()].iter().map(|i| self.allcells.cells[*i].clone()).collect()
+    }
+    // all cells which see each of the given cells
+    pub fn common_peers(&self, cells:&[&RcSudokuCell]) -> Vec<RcSudokuCell> {
+        match cells.split_first() {
+            Some((first, rest)) => self.get_peers(first).into_iter()
+                    .filter(|peer| rest.iter().all(|other| peer.sees(other)))
+                    .collect(),
+            None => vec![],
+        }
+    }
+    // the square the given cell belongs to
+    pub fn square_of(&self, cell:&RcSudokuCell) -> &Square {
+        &self.squares[square_index(cell.get_idx())]
+    }
+    // the cells which are part of both given rows, cols or squares
+    pub fn intersection(&self, a:&dyn RowColSquare, b:&dyn RowColSquare) -> Vec<RcSudokuCell> {
+        a.get_cells().iter()
+            .filter(|cell| b.contains(cell))
+            .cloned()
+            .collect()
+    }
+    // the state of all cells, to find out if something changed
+    pub fn snapshot(&self) -> Vec<CellState> {
+        self.allcells.cells.iter().map(|c| c.get_state()).collect()
+    }
+    // false if a cell is in error, a value is solved twice in a row, col or square or can not be placed in it anymore
+    pub fn is_consistent(&self) -> bool {
+        self.all_logic_squares().iter().all(|unit| {
+            let mut solved_mask = 0;
+            let mut possible_mask = 0;
+            unit.get_cells().iter().all(|cell| match cell.get_state() {
+                CellState::Solved(mask, _) => {
+                    let twice = solved_mask & mask != 0;
+                    solved_mask |= mask;
+                    !twice
+                },
+                CellState::UnSolved(mask) => {
+                    possible_mask |= mask;
+                    true
+                },
+                CellState::Error => false,
+            }) && (solved_mask | possible_mask) == CELL_RESET_MASK
+        })
+    }
+    // a deep copy with its own cells, to try something without touching this board. The undo stack is not copied
+    pub fn duplicate(&self) -> SudokuBoard {
+        let mut copy = SudokuBoard::empty();
+        copy.wire();
+        for (cell, other) in copy.allcells.cells.iter().zip(&self.allcells.cells) {
+            cell.cell.borrow_mut().value = other.get_state();
+        }
+        copy
+    }
+    // a copy with only the start values, without the steps, guesses and manual inputs done on this board
+    pub fn puzzle(&self) -> SudokuBoard {
+        let copy = self.duplicate();
+        for cell in copy.all_cells() {
+            if let CellState::Solved(_, CellActor::StartValue) = cell.get_state() {
+                continue;
+            }
+            cell.cell.borrow_mut().value = CellState::UnSolved(CELL_RESET_MASK);
+        }
+        copy
+    }
     pub fn count_solved(&self) -> (usize, usize)  {
         let mut init_count = 0;
         let mut curr_count = 0;
@@ -575,5 +765,50 @@ mod tests {
         bref.init();
         bref.check_board()
     }
+    #[test]
+    fn test_intersection() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        let cells = bref.intersection(&bref.rows[4], &bref.squares[5]);
+        let pos:Vec<(usize,usize)> = cells.iter().map(|c| c.get_pos()).collect();
+        assert_eq!(pos, vec![(5,7), (5,8), (5,9)]);
+        assert!(bref.intersection(&bref.cols[0], &bref.squares[4]).is_empty());
+    }
+    #[test]
+    fn test_sees() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        let cell = &bref.rows[4].cells[4];
+        assert!(cell.sees(&bref.rows[4].cells[8]));
+        assert!(cell.sees(&bref.rows[0].cells[4]));
+        assert!(cell.sees(&bref.rows[3].cells[5]));
+        assert!(!cell.sees(&bref.rows[3].cells[6]));
+        assert!(!cell.sees(cell));
+    }
+    #[test]
+    fn test_peers() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        for cell in bref.all_cells() {
+            assert_eq!(bref.get_peers(cell).len(), 20);
+        }
+        let a = &bref.rows[0].cells[0];
+        let b = &bref.rows[4].cells[4];
+        let pos:Vec<(usize,usize)> = bref.common_peers(&[a, b]).iter().map(|c| c.get_pos()).collect();
+        assert_eq!(pos, vec![(1,5), (5,1)]);
+    }
+    #[test]
+    fn test_duplicate() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        bref.init_cell(0, 0, 5);
+        let copy = bref.duplicate();
+        assert!(copy.snapshot() == bref.snapshot());
+        copy.get_cell(1).set_init_value(3);
+        copy.squares[0].get_cells()[2].set_init_value(4);
+        assert_eq!(copy.rows[0].cells[2].get_value(), "4");
+        assert_eq!(bref.get_cell(1).get_value(), "-");
+        assert_eq!(bref.get_cell(2).get_value(), "-");
+    }
 }
 
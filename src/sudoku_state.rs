@@ -1,9 +1,15 @@
 //use std::rc::{Rc};
 //use std::sync::Arc;
 use druid::*;
-use std::collections::*;
 use crate::sudoku_board::*;
 use crate::data::*;
+use crate::deduction::*;
+use crate::technique::*;
+use crate::dlx::*;
+use crate::rating::*;
+
+// while solving only the techniques up to this difficulty are used before guessing
+pub const SOLVE_DIFFICULTY:f32 = 7.0;
 
 #[derive(Data, Clone, PartialEq,Debug)]
 pub enum GameState{
@@ -24,6 +30,14 @@ pub struct SudokuState{
     game_state:GameState,
     print_row_details:bool,
     print_cell_details:bool,
+    #[data(ignore)]
+    pipeline:Pipeline, // the techniques, cheapest first
+    #[data(ignore)]
+    history:Vec<Vec<Deduction>>, // per step the deductions which changed the board
+    unique_solution:bool, // the puzzle is known to have only one solution, which allows the uniqueness techniques
+    solution_count:Option<SolutionCount>, // None if not counted yet
+    #[data(same_fn = "PartialEq::eq")]
+    rating:Option<Rating>, // None if not rated yet
 }
 
 
@@ -36,12 +50,21 @@ impl SudokuState {
             game_state:GameState::Select,      
             print_row_details:false,
             print_cell_details:false,
+            pipeline:Pipeline::standard(),
+            history:vec![],
+            unique_solution:false,
+            solution_count:None,
+            rating:None,
         }
     }
     pub fn reset(&mut self){
         self.step_count = 0;
         self.init_count = 0;
         self.curr_count = 0;      
+        self.unique_solution = false;
+        self.solution_count = None;
+        self.rating = None;
+        self.history = vec![];
         self.game_state = GameState::ManualInput;
     }
     pub fn select(&mut self){
@@ -52,6 +75,34 @@ impl SudokuState {
     pub fn get_curr_count(&self)-> usize { self.curr_count }
     pub fn get_state(&self)-> GameState { self.game_state.clone() }
 
+    pub fn get_pipeline(&self) -> &Pipeline { &self.pipeline }
+    pub fn get_pipeline_mut(&mut self) -> &mut Pipeline { &mut self.pipeline }
+    pub fn set_pipeline(&mut self, pipeline:Pipeline) { self.pipeline = pipeline; }
+    pub fn set_unique(&mut self, on:bool) { self.unique_solution = on; }
+    pub fn is_unique(&self) -> bool { self.unique_solution }
+    pub fn get_solution_count(&self) -> Option<SolutionCount> { self.solution_count.clone() }
+    pub fn get_rating(&self) -> Option<Rating> { self.rating.clone() }
+
+    // rate the difficulty of the puzzle without changing the board, only a puzzle with one solution is rated
+    pub fn check_rating(&mut self, board:&SudokuBoard) -> Option<Rating> {
+        self.rating = match self.solution_count {
+            Some(SolutionCount::Unique) => Some(rate(self, board)),
+            _                           => None,
+        };
+        self.rating.clone()
+    }
+    // the deductions of the last step
+    pub fn get_deductions(&self) -> &[Deduction] { self.history.last().map_or(&[], |d| d.as_slice()) }
+    pub fn get_history(&self) -> &Vec<Vec<Deduction>> { &self.history }
+
+    // count the solutions, the uniqueness techniques are only used for a puzzle with one solution
+    pub fn check_solutions(&mut self, board:&SudokuBoard) -> SolutionCount {
+        let count = count_solutions(board, COUNT_LIMIT);
+        self.unique_solution = count == SolutionCount::Unique;
+        self.solution_count = Some(count.clone());
+        count
+    }
+
     pub fn do_count( &mut self, board:&SudokuBoard) {
         let counts = board.count_solved();
         self.init_count = counts.0;
@@ -74,30 +125,124 @@ impl SudokuState {
         self.game_state.clone()
     }
 
-    pub fn resolve_step( &mut self, board:&SudokuBoard) -> GameState {
+    /**
+     * One step: apply the deductions of the cheapest technique which changes the board.
+     * Return these deductions, they are kept until the step is taken back
+     */
+    pub fn resolve_step( &mut self, board:&SudokuBoard) -> Vec<Deduction> {
         self.step_count += 1;
         board.push();
-        for square in board.all_logic_squares() {
-            match self.resolve_square(square)  {
-                Ok(_)   => self.game_state = GameState::Stepping,
-                Err(_)  => {
-                    self.game_state = GameState::Error;
-                    return self.game_state.clone()},
-            }
+        // a guess or a manual input is not yet removed from the possible values of the other cells
+        let mut result = board.all_logic_squares().iter().try_for_each(|square| self.reduce_square(*square).map(|_| ()))
+            .and_then(|_| self.resolve_board(board));
+        if result.is_ok() && !board.is_consistent() {
+            result = Err("Error: the board can not be solved anymore".to_string());
         }
+        let deductions = match result {
+            Ok(deductions) => {
+                self.game_state = GameState::Stepping;
+                deductions
+            },
+            Err(_) => {
+                self.game_state = GameState::Error;
+                vec![]
+            },
+        };
+        self.history.push(deductions.clone());
         self.do_count(board);
-        if self.curr_count == CELL_SIZE * CELL_SIZE {
-            println!("Bingo!");
+        if self.game_state == GameState::Stepping && self.curr_count == CELL_SIZE * CELL_SIZE {
             self.game_state = GameState::Solved;
         }
+        deductions
+    }
+
+    /**
+     * The simplest deduction for the next step, without changing the board:
+     * the first deduction of the cheapest technique which would change the board
+     */
+    pub fn hint(&self, board:&SudokuBoard) -> Option<Deduction> {
+        // look on a copy, a guess or a manual input is not yet removed from the possible values of the other cells
+        let copy = board.duplicate();
+        for square in copy.all_logic_squares() {
+            self.reduce_square(square).ok()?;
+        }
+        for technique in self.pipeline.techniques(self.unique_solution) {
+            for deduction in technique.find_with(&copy, &self.pipeline, self.unique_solution).ok()? {
+                copy.push();
+                let changed = deduction.apply(&copy);
+                copy.pop();
+                if changed == Ok(true) {
+                    return Some(deduction);
+                }
+            }
+        }
+        None
+    }
+
+    /**
+     * Solve the board: step with logic until stuck, then guess a value for the cell with the fewest possible values.
+     * If a guess leads to an error, step back to the guess and try the next value (backtracking).
+     */
+    pub fn solve(&mut self, board:&SudokuBoard) -> GameState {
+        // the uniqueness techniques are only used for a puzzle which is known to have one solution
+        if self.solution_count.is_none() {
+            self.check_solutions(board);
+        }
+        // guessing is cheaper than the forcing techniques, so these are left out while solving
+        let pipeline = self.pipeline.up_to(SOLVE_DIFFICULTY);
+        let pipeline = std::mem::replace(&mut self.pipeline, pipeline);
+        self.game_state = self.solve_depth(board, 1);
+        self.pipeline = pipeline;
+        self.do_count(board);
         self.game_state.clone()
     }
+    fn solve_depth(&mut self, board:&SudokuBoard, depth:usize) -> GameState {
+        loop {
+            let before = board.snapshot();
+            self.resolve_step(board);
+            match self.game_state {
+                GameState::Solved => return GameState::Solved,
+                GameState::Stepping => (),
+                _ => return GameState::Error,
+            }
+            if board.snapshot() == before {
+                break;
+            }
+        }
+        let cell = board.all_cells().iter()
+            .filter(|c| c.get_candidates() != 0)
+            .min_by_key(|c| c.get_candidates().count_ones());
+        let cell = match cell {
+            Some(c) => c.clone(),
+            None    => return GameState::Error,
+        };
+        let candidates = cell.get_candidates();
+        for value in (0..CELL_SIZE).filter(|v| candidates & (1 << v) != 0) {
+            let step_count = self.step_count;
+            // a guess is a step of its own, so it can be taken back
+            self.step_count += 1;
+            board.push();
+            cell.set_guessed_value(1 << value, depth);
+            let mut guess = Deduction::new("Guess");
+            guess.place(cell.get_idx(), 1 << value);
+            guess.reason_cells.push(cell.get_idx());
+            self.history.push(vec![guess]);
+            if self.solve_depth(board, depth + 1) == GameState::Solved {
+                return GameState::Solved;
+            }
+            while self.step_count > step_count {
+                self.step_back(board);
+            }
+        }
+        GameState::Error
+    }
 
     pub fn step_back(&mut self,  board:&SudokuBoard){
         board.pop();
         self.do_count(board);
         if self.step_count > 0 {
             self.step_count -= 1;
+            self.history.truncate(self.step_count);
             self.game_state = GameState::Stepping;
         } 
     }
@@ -133,96 +278,153 @@ impl SudokuState {
         }
         Ok(0)
     }
-    pub fn resolve_square(&self, row_col_square: &dyn RowColSquare) -> Result<usize, String> {
-        // Step 1
- 
+    /**
+     * The unit techniques of the pipeline within one row, col or square, given by its index in all_logic_squares.
+     * Return the amount of deductions which changed the board
+     */
+    pub fn resolve_square(&self, board:&SudokuBoard, unit:usize) -> Result<usize, String> {
+        let row_col_square = board.all_logic_squares()[unit];
         self.reduce_square(row_col_square)?;
- 
-        let cells = row_col_square.get_cells();
-        // Step 2 the inverse of step 1
-        // in step 1 for each given cell we investigate the possible values
-        // in step 2 for each given value investigate the possible cells
-        
-        let mut possible_cells:Vec<usize> = vec![ 0;CELL_SIZE];   // Step 1 get the resolved mask for all cells. A 1 on a bitpos means resolved.
-
-        for value in 0..CELL_SIZE {
-            let value_mask = 1<< value;
-
-            for n in 0.. CELL_SIZE{
-                let cell_mask  = 1 << n;
-                let cell = &cells[n];
-                let mask = cell.get_unresolved_mask();
-
-                if mask & value_mask == value_mask {
-                    // ok this value could placed in  tnis cell
-                    possible_cells[value] |= cell_mask;
-                }
+        if self.print_row_details {
+            print!("{:10} ", row_col_square.get_id());
+            for value in 0..CELL_SIZE {
+                print!(" {}:{} ", value + 1, row_col_square.get_value_cells(1 << value).len());
             }
+            println!();
         }
-        // step 3 find loners (resolved cells hidden in the wood of unresolved bits
-        for n in 0..CELL_SIZE {
-
-            let mask = possible_cells[n];
-            if mask.count_ones() == 0 {
-                println!("Error: in reduce square {} zero bits left for value {}  ", row_col_square.get_id(), n+1);
-
-                return Err(format!("For value {} no positions anymore",n+1));
-            } else
-            if mask.count_ones() == 1 {
-                let index = mask.trailing_zeros() as usize;
-                let cell = &cells[index];
-                let value_mask = 1<< n;
-                match cell.get_state() {
-                    CellState::UnSolved(_) => {
-                        println!("Found a loner hidden in  the bush {:?} value {}", cell.get_pos(), n +1);
-                        cell.set_solved_value(value_mask);
-                    },
-                _  => ()
-                } 
-            } 
+        let mut count = 0;
+        for technique in self.pipeline.techniques(self.unique_solution) {
+            if let Some(deductions) = technique.find_in_unit(row_col_square, unit) {
+                for deduction in deductions? {
+                    if deduction.apply(board)? {
+                        count += 1;
+                    }
+                }
+            }
         }
-    
-        // step 4 make a hasmap of the unresolved masks, and count the twins
-        let mut overall_twin_mask = 0;
-
-        let mut twin_hash:HashMap<usize,usize> = HashMap::new();   
-    
-        // for each possible value masks count the amount
-        for c in 0..CELL_SIZE {
-            let cell = &cells[c];
-            let unresolved = cell.get_unresolved_mask();
-            let result = 
-                    match twin_hash.get(&unresolved){
-                        Option::Some(n)   => n + 1,
-                        Option::None      => 1,
-                        };
-            twin_hash.insert(unresolved, result);
+        Ok(count)
+    }
+
+    /**
+     * Try the techniques of the pipeline in order, until one of them changes the board
+     * Return the deductions which changed the board
+     */
+    pub fn resolve_board(&self, board:&SudokuBoard) -> Result<Vec<Deduction>, String> {
+        for technique in self.pipeline.techniques(self.unique_solution) {
+            let applied = self.apply_deductions(board, technique.find_with(board, &self.pipeline, self.unique_solution)?)?;
+            if !applied.is_empty() {
+                return Ok(applied);
+            }
         }
-        // now iterate over the map and find the twins
-        for (key, val) in twin_hash.iter() {
-            if key.count_ones () == 2 && *val == (2  as usize) {
-                println!("Found a twin2 in {} (not yet resolved). Twin   mask {:09b}", row_col_square.get_id(), key  );
-                overall_twin_mask |= key;
+        Ok(vec![])
+    }
+
+    /**
+     * Apply the deductions found by a technique on the board
+     * Return the deductions which changed the board
+     */
+    pub fn apply_deductions(&self, board:&SudokuBoard, deductions:Vec<Deduction>) -> Result<Vec<Deduction>, String> {
+        let mut applied = vec![];
+        for deduction in deductions {
+            if deduction.apply(board)? {
+                applied.push(deduction);
             }
         }
-        if overall_twin_mask > 0{
-            println!("Reduce {} due to found twin mask {:09b} ",  row_col_square.get_id(), overall_twin_mask);
-            for c in 0..CELL_SIZE {
-                let cell = &cells[c];
-                cell.reduce(overall_twin_mask);
+        Ok(applied)
+    }
+
+    /********************************************************************************************************** */
+}
+
+/**
+ * Locked candidates (pointing): if within a square a value can only be placed in one row or col,
+ * the value can not be placed in the rest of that row or col
+ */
+pub fn find_pointing(board:&SudokuBoard) -> Vec<Deduction> {
+    let mut result = vec![];
+    for (s, square) in board.squares.iter().enumerate() {
+        for (l, line) in board.all_lines().iter().enumerate() {
+            let segment = board.intersection(square, *line);
+            if segment.is_empty() {
+                continue;
+            }
+            for value in 0..CELL_SIZE {
+                let value_mask = 1 << value;
+                let in_square = square.get_value_cells(value_mask);
+                let in_segment:Vec<&RcSudokuCell> = segment.iter().filter(|c| c.get_candidates() & value_mask != 0).collect();
+                if in_square.len() < 2 || in_segment.len() != in_square.len() {
+                    continue;
+                }
+                let mut deduction = Deduction::new("Pointing");
+                deduction.reason_cells = in_segment.iter().map(|c| c.get_idx()).collect();
+                deduction.units = vec![2 * CELL_SIZE + s, l];
+                for cell in line.get_value_cells(value_mask) {
+                    if !square.contains(&cell) {
+                        deduction.eliminate(cell.get_idx(), value_mask);
+                    }
+                }
+                if !deduction.is_empty() {
+                    result.push(deduction);
+                }
             }
         }
-        // Show the results on the terminal
-        if self.print_row_details {
-            print!("{:10} ", row_col_square.get_id());
-            for n in 0..CELL_SIZE {
-                print!(" {}:{:09b} ", n+1, possible_cells[n] );
+    }
+    result
+}
+
+/**
+ * Box/line reduction (claiming): if within a row or col a value can only be placed in one square,
+ * the value can not be placed in the rest of that square
+ */
+pub fn find_claiming(board:&SudokuBoard) -> Vec<Deduction> {
+    let mut result = vec![];
+    for (l, line) in board.all_lines().iter().enumerate() {
+        for value in 0..CELL_SIZE {
+            let value_mask = 1 << value;
+            let value_cells = line.get_value_cells(value_mask);
+            if value_cells.len() < 2 {
+                continue;
+            }
+            let square = board.square_of(&value_cells[0]);
+            if !value_cells.iter().all(|c| square.contains(c)) {
+                continue;
+            }
+            let mut deduction = Deduction::new("Claiming");
+            deduction.reason_cells = value_cells.iter().map(|c| c.get_idx()).collect();
+            deduction.units = vec![l, 2 * CELL_SIZE + square_index(value_cells[0].get_idx())];
+            for cell in square.get_value_cells(value_mask) {
+                if !line.contains(&cell) {
+                    deduction.eliminate(cell.get_idx(), value_mask);
+                }
+            }
+            if !deduction.is_empty() {
+                result.push(deduction);
             }
-            println!();
         }
-        Ok(0)
     }
-    /********************************************************************************************************** */
+    result
+}
+
+/**
+ * All combinations of size elements out of the given items, in the order of the items
+ */
+pub fn combinations(items:&[usize], size:usize) -> Vec<Vec<usize>> {
+    let mut result = vec![];
+    let mut current = vec![];
+    combine(items, size, 0, &mut current, &mut result);
+    result
+}
+
+fn combine(items:&[usize], size:usize, start:usize, current:&mut Vec<usize>, result:&mut Vec<Vec<usize>>) {
+    if current.len() == size {
+        result.push(current.clone());
+        return;
+    }
+    for i in start..items.len() {
+        current.push(items[i]);
+        combine(items, size, i + 1, current, result);
+        current.pop();
+    }
 }
 
 
@@ -251,11 +453,165 @@ mod tests {
         }
     }
     #[test]
+    fn test_deductions() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        bref.init();
+        let  mut state = SudokuState::new();
+        state.reduce_step(bref);
+        let deductions = state.resolve_step(bref);
+        assert!(!deductions.is_empty());
+        assert!(deductions.iter().all(|d| d.technique == "Hidden Single" && d.units.len() == 1));
+        assert_eq!(state.get_deductions(), &deductions[..]);
+        state.step_back(bref);
+        assert!(state.get_history().is_empty());
+    }
+    #[test]
+    fn test_hint() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        bref.init();
+        let  mut state = SudokuState::new();
+        state.reduce_step(bref);
+        let before = bref.snapshot();
+        let hint = state.hint(bref).unwrap();
+        assert!(bref.snapshot() == before);
+        assert_eq!(hint.technique, "Hidden Single");
+        assert_eq!(state.resolve_step(bref)[0], hint);
+    }
+    #[test]
     fn test_check_board() { 
         let  mut board = SudokuBoard::new();
         let bref = board.wire();
         bref.init();
         bref.check_board()
     }
+    #[test]
+    fn test_solve() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // a solved board with r4c2, r4c5, r5c2 and r5c5 open: they can be 1 6 6 1 or 6 1 1 6
+        let solution = "946531827357826149182947536269415378513768492478392615694283751835179264721654983";
+        for (idx, c) in solution.chars().enumerate() {
+            if ![28, 31, 37, 40].contains(&idx) {
+                bref.get_cell(idx).set_init_value(c.to_digit(10).unwrap() as usize);
+            }
+        }
+        let  mut state = SudokuState::new();
+        assert!(state.solve(bref) == GameState::Solved);
+        assert!(bref.is_consistent());
+        assert_eq!(state.get_curr_count(), CELL_COUNT);
+        assert!(bref.get_cell(28).get_state() == CellState::Solved(0b000000001, CellActor::Guessed(1)));
+        assert_eq!(bref.get_cell(40).get_value(), "1");
+    }
+    #[test]
+    fn test_solve_several_solutions() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // with one solution assumed, the unique rectangles of this puzzle lead to an error
+        let puzzle = "...53.8273..826149.82947536.69..5.7.51..6.4.2..8.9.6156.4.....18.517.26.72...4983";
+        for (idx, c) in puzzle.chars().enumerate() {
+            if let Some(value) = c.to_digit(10) {
+                bref.get_cell(idx).set_init_value(value as usize);
+            }
+        }
+        let  mut state = SudokuState::new();
+        assert!(state.solve(bref) == GameState::Solved);
+        assert!(bref.is_consistent());
+        assert_eq!(state.get_curr_count(), CELL_COUNT);
+        assert!(matches!(state.get_solution_count(), Some(SolutionCount::AtLeast(_))));
+        assert!(!state.is_unique());
+        let techniques:Vec<String> = state.get_history().iter().flatten().map(|d| d.technique.clone()).collect();
+        assert!(techniques.contains(&"Guess".to_string()));
+        assert!(!techniques.iter().any(|t| t.starts_with("Unique Rectangle")));
+    }
+    #[test]
+    fn test_check_solutions() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        bref.init();
+        let  mut state = SudokuState::new();
+        // no uniqueness techniques until the solutions are counted
+        assert!(!state.is_unique());
+        assert_eq!(state.check_solutions(bref), SolutionCount::AtLeast(COUNT_LIMIT));
+        assert!(!state.is_unique());
+        assert_eq!(state.get_solution_count(), Some(SolutionCount::AtLeast(COUNT_LIMIT)));
+        // a puzzle with more than one solution is not rated
+        assert_eq!(state.check_rating(bref), None);
+        assert_eq!(state.get_rating(), None);
+        state.set_unique(true);
+        state.reset();
+        assert!(!state.is_unique());
+        assert_eq!(state.get_solution_count(), None);
+    }
+    #[test]
+    fn test_hidden_pair() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // value 1 and 2 can only be placed in the first two cells of row 1
+        for cell in &bref.rows[0].cells[2..] {
+            cell.reduce(0b000000011).ok();
+        }
+        let mut state = SudokuState::new();
+        // the unit techniques can be switched off as well
+        state.get_pipeline_mut().set_enabled("Hidden Pair", false);
+        assert_eq!(state.resolve_square(bref, 0), Ok(0));
+        assert_eq!(bref.rows[0].cells[0].get_unresolved_mask(), 0b111111111);
+        state.get_pipeline_mut().set_enabled("Hidden Pair", true);
+        assert_eq!(state.resolve_square(bref, 0), Ok(1));
+        assert_eq!(bref.rows[0].cells[0].get_unresolved_mask(), 0b000000011);
+        assert_eq!(bref.rows[0].cells[1].get_unresolved_mask(), 0b000000011);
+    }
+    #[test]
+    fn test_naked_triple() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // the first three cells of col 1 contain {12}, {23} and {13}
+        let cells = bref.cols[0].get_cells();
+        cells[0].reduce(0b111111100).ok();
+        cells[1].reduce(0b111111001).ok();
+        cells[2].reduce(0b111111010).ok();
+        let state = SudokuState::new();
+        assert!(state.resolve_square(bref, CELL_SIZE).is_ok());
+        for cell in &cells[3..] {
+            assert_eq!(cell.get_unresolved_mask() & 0b000000111, 0);
+        }
+        assert_eq!(cells[0].get_unresolved_mask(), 0b000000011);
+    }
+    #[test]
+    fn test_pointing() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // within square 1 value 1 can only be placed in row 1
+        for cell in &bref.squares[0].get_cells()[3..] {
+            cell.reduce(0b000000001).ok();
+        }
+        let deductions = find_pointing(bref);
+        assert_eq!(deductions.len(), 1);
+        assert_eq!(deductions[0].eliminations.len(), 6);
+        assert_eq!(deductions[0].apply(bref), Ok(true));
+        for cell in &bref.rows[0].cells[3..] {
+            assert_eq!(cell.get_unresolved_mask() & 0b000000001, 0);
+        }
+        assert_eq!(bref.rows[0].cells[0].get_unresolved_mask(), 0b111111111);
+    }
+    #[test]
+    fn test_claiming() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // within col 1 value 9 can only be placed in square 1
+        for cell in &bref.cols[0].get_cells()[3..] {
+            cell.reduce(0b100000000).ok();
+        }
+        let deductions = find_claiming(bref);
+        assert_eq!(deductions.len(), 1);
+        assert_eq!(deductions[0].units, vec![CELL_SIZE, 2 * CELL_SIZE]);
+        assert_eq!(deductions[0].eliminations.len(), 6);
+        assert_eq!(deductions[0].apply(bref), Ok(true));
+        for cell in bref.squares[0].get_cells() {
+            let expected = if cell.get_pos().1 == 1 { 0b100000000 } else { 0 };
+            assert_eq!(cell.get_unresolved_mask() & 0b100000000, expected);
+        }
+    }
 }
 
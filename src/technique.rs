@@ -0,0 +1,274 @@
+use std::fmt;
+use std::rc::Rc;
+use crate::sudoku_board::*;
+use crate::sudoku_state::*;
+use crate::deduction::*;
+use crate::basic::*;
+use crate::fish::*;
+use crate::wings::*;
+use crate::single_digit::*;
+use crate::coloring::*;
+use crate::chains::*;
+use crate::aic::*;
+use crate::uniqueness::*;
+use crate::als::*;
+use crate::forcing::*;
+
+/**
+ * A solving technique which looks at the board and finds deductions, without changing the board.
+ * The difficulty is a weight in the style of the Sudoku Explainer rating: the cheaper, the earlier it is tried.
+ */
+pub trait Technique {
+    fn name(&self) -> &str;
+    fn difficulty(&self) -> f32;
+    // the uniqueness techniques are only sound for a puzzle with one solution
+    fn needs_unique(&self) -> bool { false }
+    // Err if the board can not be solved anymore
+    fn find(&self, board:&SudokuBoard) -> Result<Vec<Deduction>, String>;
+    // the deductions within one row, col or square (unit is its index in all_logic_squares), None if the technique looks at the whole board
+    fn find_in_unit(&self, _row_col_square:&dyn RowColSquare, _unit:usize) -> Option<Result<Vec<Deduction>, String>> { None }
+    // find with the pipeline of the caller, for the techniques which follow assumptions with the other techniques
+    fn find_with(&self, board:&SudokuBoard, _pipeline:&Pipeline, _unique:bool) -> Result<Vec<Deduction>, String> { self.find(board) }
+}
+
+/**
+ * A technique made of one of the find functions
+ */
+pub struct Finder {
+    name: &'static str,
+    difficulty: f32,
+    needs_unique: bool,
+    finder: fn(&SudokuBoard) -> Vec<Deduction>,
+}
+
+impl Finder {
+    pub fn new(name:&'static str, difficulty:f32, finder:fn(&SudokuBoard) -> Vec<Deduction>) -> Finder {
+        Finder { name, difficulty, needs_unique: false, finder }
+    }
+    pub fn unique(name:&'static str, difficulty:f32, finder:fn(&SudokuBoard) -> Vec<Deduction>) -> Finder {
+        Finder { name, difficulty, needs_unique: true, finder }
+    }
+}
+
+impl Technique for Finder {
+    fn name(&self) -> &str { self.name }
+    fn difficulty(&self) -> f32 { self.difficulty }
+    fn needs_unique(&self) -> bool { self.needs_unique }
+    fn find(&self, board:&SudokuBoard) -> Result<Vec<Deduction>, String> { Ok((self.finder)(board)) }
+}
+
+/**
+ * A technique within one row, col or square made of one of the unit functions, Err if the unit can not be solved anymore
+ */
+pub struct UnitFinder {
+    name: &'static str,
+    difficulty: f32,
+    finder: fn(&dyn RowColSquare, usize) -> Result<Vec<Deduction>, String>,
+}
+
+impl UnitFinder {
+    pub fn new(name:&'static str, difficulty:f32, finder:fn(&dyn RowColSquare, usize) -> Result<Vec<Deduction>, String>) -> UnitFinder {
+        UnitFinder { name, difficulty, finder }
+    }
+}
+
+impl Technique for UnitFinder {
+    fn name(&self) -> &str { self.name }
+    fn difficulty(&self) -> f32 { self.difficulty }
+    fn find(&self, board:&SudokuBoard) -> Result<Vec<Deduction>, String> { find_in_units(board, self.finder) }
+    fn find_in_unit(&self, row_col_square:&dyn RowColSquare, unit:usize) -> Option<Result<Vec<Deduction>, String>> {
+        Some((self.finder)(row_col_square, unit))
+    }
+}
+
+/**
+ * A forcing technique: the consequences of the assumptions are followed once per find,
+ * with the techniques of the caller's pipeline, and passed to one of the forcing functions
+ */
+pub struct Forcing {
+    name: &'static str,
+    difficulty: f32,
+    finder: fn(&SudokuBoard, &[Branch]) -> Vec<Deduction>,
+}
+
+impl Forcing {
+    pub fn new(name:&'static str, difficulty:f32, finder:fn(&SudokuBoard, &[Branch]) -> Vec<Deduction>) -> Forcing {
+        Forcing { name, difficulty, finder }
+    }
+}
+
+impl Technique for Forcing {
+    fn name(&self) -> &str { self.name }
+    fn difficulty(&self) -> f32 { self.difficulty }
+    fn find(&self, board:&SudokuBoard) -> Result<Vec<Deduction>, String> { self.find_with(board, &Pipeline::standard(), false) }
+    fn find_with(&self, board:&SudokuBoard, pipeline:&Pipeline, unique:bool) -> Result<Vec<Deduction>, String> {
+        Ok((self.finder)(board, &assumptions(board, pipeline, unique)))
+    }
+}
+
+/**
+ * The ordered techniques the solver tries, each one can be switched off.
+ * Add keeps the order cheapest first, insert and move_to allow another order.
+ */
+#[derive(Clone, Default)]
+pub struct Pipeline {
+    steps: Vec<(Rc<dyn Technique>, bool)>,  // the technique and whether it is enabled
+}
+
+impl Pipeline {
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+    // all techniques of this crate
+    pub fn standard() -> Pipeline {
+        let mut pipeline = Pipeline::new();
+        let techniques:Vec<Rc<dyn Technique>> = vec![
+            Rc::new(UnitFinder::new("Hidden Single", 1.5, hidden_singles)),
+            Rc::new(Finder::new("Naked Single", 2.3, find_naked_single)),
+            Rc::new(Finder::new("Pointing", 2.6, find_pointing)),
+            Rc::new(Finder::new("Claiming", 2.8, find_claiming)),
+            Rc::new(UnitFinder::new("Naked Pair", 3.0, |unit, n| naked_subsets(unit, n, 2))),
+            Rc::new(Finder::new("X-Wing", 3.2, |b| find_fish(b, 2))),
+            Rc::new(UnitFinder::new("Hidden Pair", 3.4, |unit, n| hidden_subsets(unit, n, 2))),
+            Rc::new(Finder::new("Finned X-Wing", 3.4, |b| find_finned_fish(b, 2))),
+            Rc::new(UnitFinder::new("Naked Triple", 3.6, |unit, n| naked_subsets(unit, n, 3))),
+            Rc::new(Finder::new("Swordfish", 3.8, |b| find_fish(b, 3))),
+            Rc::new(UnitFinder::new("Hidden Triple", 4.0, |unit, n| hidden_subsets(unit, n, 3))),
+            Rc::new(Finder::new("Finned Swordfish", 4.0, |b| find_finned_fish(b, 3))),
+            Rc::new(Finder::new("Skyscraper", 4.0, find_skyscraper)),
+            Rc::new(Finder::new("2-String Kite", 4.1, find_two_string_kite)),
+            Rc::new(Finder::new("XY-Wing", 4.2, find_xy_wing)),
+            Rc::new(Finder::new("Empty Rectangle", 4.3, find_empty_rectangle)),
+            Rc::new(Finder::new("XYZ-Wing", 4.4, find_xyz_wing)),
+            Rc::new(Finder::new("W-Wing", 4.4, find_w_wing)),
+            Rc::new(Finder::unique("Unique Rectangle", 4.5, find_unique_rectangle)),
+            Rc::new(Finder::new("Simple Coloring", 4.5, find_simple_coloring)),
+            Rc::new(Finder::new("Multi-Coloring", 5.0, find_multi_coloring)),
+            Rc::new(Finder::new("Sue de Coq", 5.0, find_sue_de_coq)),
+            Rc::new(UnitFinder::new("Naked Quad", 5.0, |unit, n| naked_subsets(unit, n, 4))),
+            Rc::new(Finder::new("Jellyfish", 5.2, |b| find_fish(b, 4))),
+            Rc::new(UnitFinder::new("Hidden Quad", 5.4, |unit, n| hidden_subsets(unit, n, 4))),
+            Rc::new(Finder::new("Finned Jellyfish", 5.4, |b| find_finned_fish(b, 4))),
+            Rc::new(Finder::new("ALS-XZ", 5.5, find_als_xz)),
+            Rc::new(Finder::unique("BUG+1", 5.6, find_bug_plus_one)),
+            Rc::new(Finder::new("ALS-XY-Wing", 6.0, find_als_xy_wing)),
+            Rc::new(Finder::new("X-Chain", 6.5, find_x_chain)),
+            Rc::new(Finder::new("XY-Chain", 6.6, find_xy_chain)),
+            Rc::new(Finder::new("Death Blossom", 6.6, find_death_blossom)),
+            Rc::new(Finder::new("AIC", 7.0, find_aic)),
+            // the last resort before guessing: try each value and follow the consequences
+            Rc::new(Forcing::new("Nishio", 7.5, find_nishio)),
+            Rc::new(Forcing::new("Cell Forcing Chain", 8.3, find_cell_forcing_chain)),
+            Rc::new(Forcing::new("Unit Forcing Chain", 8.5, find_unit_forcing_chain)),
+        ];
+        for technique in techniques {
+            pipeline.add_rc(technique);
+        }
+        pipeline
+    }
+
+    // add a technique after the ones which are not more difficult
+    pub fn add<T:Technique + 'static>(&mut self, technique:T) {
+        self.add_rc(Rc::new(technique));
+    }
+    fn add_rc(&mut self, technique:Rc<dyn Technique>) {
+        let pos = self.steps.iter().take_while(|(t, _)| t.difficulty() <= technique.difficulty()).count();
+        self.steps.insert(pos, (technique, true));
+    }
+    pub fn insert<T:Technique + 'static>(&mut self, pos:usize, technique:T) {
+        self.insert_rc(pos, Rc::new(technique));
+    }
+    fn insert_rc(&mut self, pos:usize, technique:Rc<dyn Technique>) {
+        self.steps.insert(pos.min(self.steps.len()), (technique, true));
+    }
+    pub fn remove(&mut self, name:&str) -> Option<Rc<dyn Technique>> {
+        let pos = self.position(name)?;
+        Some(self.steps.remove(pos).0)
+    }
+    // move a technique to another position, return false if it is not in the pipeline
+    pub fn move_to(&mut self, name:&str, pos:usize) -> bool {
+        let enabled = self.is_enabled(name);
+        match self.remove(name) {
+            Some(technique) => {
+                self.insert_rc(pos, technique);
+                self.set_enabled(name, enabled);
+                true
+            },
+            None => false,
+        }
+    }
+    pub fn set_enabled(&mut self, name:&str, on:bool) -> bool {
+        match self.position(name) {
+            Some(pos) => { self.steps[pos].1 = on; true },
+            None => false,
+        }
+    }
+    pub fn is_enabled(&self, name:&str) -> bool {
+        self.steps.iter().any(|(t, on)| *on && t.name() == name)
+    }
+    pub fn names(&self) -> Vec<&str> {
+        self.steps.iter().map(|(t, _)| t.name()).collect()
+    }
+    pub fn get(&self, name:&str) -> Option<Rc<dyn Technique>> {
+        self.position(name).map(|pos| self.steps[pos].0.clone())
+    }
+
+    // a copy without the techniques which are more difficult
+    pub fn up_to(&self, difficulty:f32) -> Pipeline {
+        let steps = self.steps.iter().filter(|(t, _)| t.difficulty() <= difficulty).cloned().collect();
+        Pipeline { steps }
+    }
+
+    // the enabled techniques in order, without the uniqueness techniques if the solution might not be unique
+    pub fn techniques(&self, unique:bool) -> Vec<Rc<dyn Technique>> {
+        self.steps.iter()
+            .filter(|(t, on)| *on && (unique || !t.needs_unique()))
+            .map(|(t, _)| t.clone())
+            .collect()
+    }
+
+    fn position(&self, name:&str) -> Option<usize> {
+        self.steps.iter().position(|(t, _)| t.name() == name)
+    }
+}
+
+impl fmt::Debug for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let steps:Vec<String> = self.steps.iter()
+            .map(|(t, on)| format!("{} {:.1}{}", t.name(), t.difficulty(), if *on { "" } else { " (off)" }))
+            .collect();
+        write!(f, "Pipeline [{}]", steps.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pipeline() {
+        let mut pipeline = Pipeline::standard();
+        let names = pipeline.names();
+        assert_eq!(names[0], "Hidden Single");
+        assert_eq!(names[names.len() - 1], "Unit Forcing Chain");
+        let difficulties:Vec<f32> = pipeline.techniques(true).iter().map(|t| t.difficulty()).collect();
+        assert!(difficulties.windows(2).all(|w| w[0] <= w[1]));
+
+        assert!(pipeline.set_enabled("X-Wing", false));
+        assert!(!pipeline.is_enabled("X-Wing"));
+        assert!(pipeline.techniques(true).iter().all(|t| t.name() != "X-Wing"));
+        assert!(pipeline.techniques(false).iter().all(|t| !t.needs_unique()));
+
+        assert!(pipeline.move_to("AIC", 0));
+        assert_eq!(pipeline.names()[0], "AIC");
+        assert!(pipeline.remove("AIC").is_some());
+        assert!(!pipeline.move_to("AIC", 0));
+        pipeline.add(Finder::new("AIC", 7.0, find_aic));
+        assert_eq!(pipeline.techniques(true).len(), pipeline.names().len() - 1);
+
+        let easy = pipeline.up_to(7.0);
+        assert_eq!(easy.names()[easy.names().len() - 1], "AIC");
+        assert!(!easy.is_enabled("X-Wing"));
+        assert!(easy.get("Nishio").is_none());
+    }
+}
@@ -0,0 +1,16 @@
+use crate::sudoku_board::*;
+
+// the fixtures of the technique tests
+
+// value 1 can only be placed in the given positions of the row, col or square
+pub fn only_at(unit:&dyn RowColSquare, keep:&[usize]) {
+    for (n, cell) in unit.get_cells().iter().enumerate() {
+        if !keep.contains(&n) {
+            cell.reduce(0b001).ok();
+        }
+    }
+}
+// the cell can only contain the values in the mask
+pub fn keep(cell:&RcSudokuCell, mask:usize) {
+    cell.reduce(0b111111111 & !mask).ok();
+}
@@ -0,0 +1,329 @@
+use crate::sudoku_board::*;
+use crate::sudoku_state::*;
+use crate::deduction::*;
+use crate::data::*;
+
+/**
+ * Unique Rectangles: four cells in two rows, two cols and two squares, which all can contain the values XY.
+ * If all four would only contain XY the puzzle would have two solutions (the deadly pattern), 
+ * so for a puzzle with a unique solution at least one of the cells (the roof) must get another value.
+ * The floor cells only contain XY, the roof cells contain extra values.
+ * Type 1: only one roof cell, it can not be X or Y.
+ * Type 2: two roof cells in the same row or col with one and the same extra value Z, one of them will be Z.
+ * Type 3: two roof cells in one row, col or square act as one cell with the extra values, forming a naked subset.
+ * Type 4: two roof cells in one row, col or square in which X can only be placed in the roof, so Y is not possible.
+ * Type 5: two or three roof cells not in line with one and the same extra value Z, one of them will be Z.
+ * Type 6: two roof cells on a diagonal and X can only be placed in the rectangle in both rows (or cols), so X can not be in the roof.
+ * Only valid for puzzles with a unique solution.
+ */
+pub fn find_unique_rectangle(board:&SudokuBoard) -> Vec<Deduction> {
+    let mut result:Vec<Deduction> = vec![];
+    let lines:Vec<usize> = (0..CELL_SIZE).collect();
+    for rows in combinations(&lines, 2) {
+        for cols in combinations(&lines, 2) {
+            let rect:Vec<&RcSudokuCell> = [(rows[0], cols[0]), (rows[0], cols[1]), (rows[1], cols[0]), (rows[1], cols[1])].iter()
+                .map(|(r, c)| board.get_cell(r * CELL_SIZE + c))
+                .collect();
+            // the rectangle must be spread over two squares
+            if (rows[0] / 3 == rows[1] / 3) == (cols[0] / 3 == cols[1] / 3) {
+                continue;
+            }
+            let common = rect.iter().fold(!0, |mask, cell| mask & cell.get_candidates());
+            for pair in combinations(&(0..CELL_SIZE).filter(|v| common & (1 << v) != 0).collect::<Vec<usize>>(), 2) {
+                let pair_mask = (1 << pair[0]) | (1 << pair[1]);
+                for deduction in rectangle_deductions(board, &rect, pair_mask) {
+                    if !deduction.is_empty() && !result.iter().any(|d| d.eliminations == deduction.eliminations) {
+                        result.push(deduction);
+                    }
+                }
+            }
+        }
+    }
+    result
+}
+
+/**
+ * BUG+1: a bivalue universal grave has every unsolved cell with two values, and every value twice in each row, col and square.
+ * Such a grave has no or two solutions, so with one cell with three values, which form a grave without one of them,
+ * this cell must get that value: it appears three times in its row, col and square.
+ * Only valid for puzzles with a unique solution.
+ */
+pub fn find_bug_plus_one(board:&SudokuBoard) -> Vec<Deduction> {
+    let open:Vec<&RcSudokuCell> = board.all_cells().iter().filter(|c| c.get_candidates() != 0).collect();
+    let others:Vec<&&RcSudokuCell> = open.iter().filter(|c| c.get_candidates().count_ones() != 2).collect();
+    if others.len() != 1 || others[0].get_candidates().count_ones() != 3 {
+        return vec![];
+    }
+    let cell = others[0];
+    let mut result = vec![];
+    for value in (0..CELL_SIZE).filter(|v| cell.get_candidates() & (1 << v) != 0) {
+        // without this value in the cell, each value must be possible in no or two cells of every row, col and square
+        let grave = board.all_logic_squares().iter().all(|unit| (0..CELL_SIZE).all(|v| {
+            let count = unit.get_value_cells(1 << v).iter()
+                .filter(|c| v != value || c.get_idx() != cell.get_idx())
+                .count();
+            count == 0 || count == 2
+        }));
+        if grave {
+            let mut deduction = Deduction::new("BUG+1");
+            deduction.reason_cells = vec![cell.get_idx()];
+            deduction.reason_cells.extend(open.iter().map(|c| c.get_idx()).filter(|idx| *idx != cell.get_idx()));
+            deduction.units = board.all_logic_squares().iter().enumerate()
+                .filter(|(_, unit)| unit.contains(cell))
+                .map(|(n, _)| n)
+                .collect();
+            deduction.place(cell.get_idx(), 1 << value);
+            result.push(deduction);
+        }
+    }
+    result
+}
+
+fn rectangle_deductions(board:&SudokuBoard, rect:&[&RcSudokuCell], pair_mask:usize) -> Vec<Deduction> {
+    let mut result = vec![];
+    let roof:Vec<&RcSudokuCell> = rect.iter().filter(|c| c.get_candidates() != pair_mask).cloned().collect();
+    let extra = roof.iter().fold(0, |mask, cell| mask | cell.get_candidates()) & !pair_mask;
+    if roof.len() == 1 {
+        let mut deduction = rectangle_deduction("Unique Rectangle Type 1", rect);
+        deduction.eliminate(roof[0].get_idx(), pair_mask);
+        result.push(deduction);
+        return result;
+    }
+    if roof.len() > 1 && extra.count_ones() == 1 && roof.len() < 4 {
+        let in_line = roof.len() == 2 && (roof[0].get_row() == roof[1].get_row() || roof[0].get_col() == roof[1].get_col());
+        let name = if in_line { "Unique Rectangle Type 2" } else { "Unique Rectangle Type 5" };
+        let mut deduction = rectangle_deduction(name, rect);
+        for peer in board.common_peers(&roof) {
+            if peer.get_candidates() & extra != 0 && !rect.iter().any(|c| c.get_idx() == peer.get_idx()) {
+                deduction.eliminate(peer.get_idx(), extra);
+            }
+        }
+        result.push(deduction);
+    }
+    if roof.len() != 2 {
+        return result;
+    }
+    let units:Vec<&dyn RowColSquare> = board.all_logic_squares().into_iter()
+        .filter(|unit| unit.contains(roof[0]) && unit.contains(roof[1]))
+        .collect();
+    for unit in &units {
+        result.extend(type_3(rect, *unit, extra));
+        // type 4: one of the pair values can only be in the roof within the unit
+        for value_mask in [pair_mask & pair_mask.wrapping_neg(), pair_mask & (pair_mask - 1)].iter() {
+            if unit.get_value_cells(*value_mask).len() == 2 {
+                let mut deduction = rectangle_deduction("Unique Rectangle Type 4", rect);
+                for cell in &roof {
+                    deduction.eliminate(cell.get_idx(), pair_mask & !value_mask);
+                }
+                result.push(deduction);
+            }
+        }
+    }
+    if units.is_empty() {
+        // type 6: the roof is a diagonal, a pair value forms an X-Wing on the rectangle
+        for value_mask in [pair_mask & pair_mask.wrapping_neg(), pair_mask & (pair_mask - 1)].iter() {
+            let x_wing = |lines:Vec<&dyn RowColSquare>| lines.iter().all(|line| {
+                let cells = line.get_value_cells(*value_mask);
+                cells.len() == 2 && cells.iter().all(|c| rect.iter().any(|r| r.get_idx() == c.get_idx()))
+            });
+            let rows:Vec<&dyn RowColSquare> = vec![&board.rows[rect[0].get_row()], &board.rows[rect[3].get_row()]];
+            let cols:Vec<&dyn RowColSquare> = vec![&board.cols[rect[0].get_col()], &board.cols[rect[3].get_col()]];
+            if x_wing(rows) || x_wing(cols) {
+                let mut deduction = rectangle_deduction("Unique Rectangle Type 6", rect);
+                for cell in &roof {
+                    deduction.eliminate(cell.get_idx(), *value_mask);
+                }
+                result.push(deduction);
+            }
+        }
+    }
+    result
+}
+
+// type 3: the roof cells together with other cells of the unit form a naked subset
+fn type_3(rect:&[&RcSudokuCell], unit:&dyn RowColSquare, extra:usize) -> Vec<Deduction> {
+    let mut result = vec![];
+    let others:Vec<&RcSudokuCell> = unit.get_cells().iter()
+        .filter(|c| c.get_candidates() != 0 && !rect.iter().any(|r| r.get_idx() == c.get_idx()))
+        .collect();
+    for size in 2..=4 {
+        for combo in combinations(&(0..others.len()).collect::<Vec<usize>>(), size - 1) {
+            let subset_mask = combo.iter().fold(extra, |mask, i| mask | others[*i].get_candidates());
+            if subset_mask.count_ones() as usize != size {
+                continue;
+            }
+            let mut deduction = rectangle_deduction("Unique Rectangle Type 3", rect);
+            deduction.reason_cells.extend(combo.iter().map(|i| others[*i].get_idx()));
+            for (i, cell) in others.iter().enumerate() {
+                if !combo.contains(&i) && cell.get_candidates() & subset_mask != 0 {
+                    deduction.eliminate(cell.get_idx(), cell.get_candidates() & subset_mask);
+                }
+            }
+            result.push(deduction);
+        }
+    }
+    result
+}
+
+// the rows, cols and squares of the rectangle are the units
+fn rectangle_deduction(name:&str, rect:&[&RcSudokuCell]) -> Deduction {
+    let mut deduction = Deduction::new(name);
+    deduction.reason_cells = rect.iter().map(|c| c.get_idx()).collect();
+    for cell in rect {
+        for unit in [cell.get_row(), CELL_SIZE + cell.get_col(), 2 * CELL_SIZE + square_index(cell.get_idx())].iter() {
+            if !deduction.units.contains(unit) {
+                deduction.units.push(*unit);
+            }
+        }
+    }
+    deduction.units.sort();
+    deduction
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+
+    #[test]
+    fn test_unique_rectangle_type_1() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // r1c1, r1c4 and r2c1 only 1 or 2, r2c4 can not be 1 or 2
+        for idx in [0, 3, 9].iter() {
+            bref.get_cell(*idx).reduce(0b111111100).ok();
+        }
+        bref.get_cell(12).reduce(0b111111000).ok();
+        let result = find_unique_rectangle(bref);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].technique, "Unique Rectangle Type 1");
+        assert_eq!(result[0].eliminations, vec![(12, 0b000000011)]);
+        assert_eq!(result[0].units, vec![0, 1, 9, 12, 18, 19]);
+    }
+    #[test]
+    fn test_unique_rectangle_type_2() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // r1c1 and r1c4 only 1 or 2, r2c1 and r2c4 only 1, 2 or 3: one of them is 3
+        for idx in [0, 3].iter() {
+            keep(bref.get_cell(*idx), 0b011);
+        }
+        for idx in [9, 12].iter() {
+            keep(bref.get_cell(*idx), 0b111);
+        }
+        let result = find_unique_rectangle(bref);
+        let type_2 = result.iter().find(|d| d.technique == "Unique Rectangle Type 2");
+        assert!(type_2.is_some());
+        assert_eq!(type_2.unwrap().eliminations, vec![(10, 0b100), (11, 0b100), (13, 0b100), (14, 0b100), (15, 0b100), (16, 0b100), (17, 0b100)]);
+    }
+    #[test]
+    fn test_unique_rectangle_type_3() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // r1c1 and r1c4 only 1 or 2, r2c1 {123} and r2c4 {124} form a naked pair 34 with r2c7 {34}
+        for idx in [0, 3].iter() {
+            keep(bref.get_cell(*idx), 0b0011);
+        }
+        keep(bref.get_cell(9), 0b0111);
+        keep(bref.get_cell(12), 0b1011);
+        keep(bref.get_cell(15), 0b1100);
+        let result = find_unique_rectangle(bref);
+        let type_3 = result.iter().find(|d| d.technique == "Unique Rectangle Type 3");
+        assert!(type_3.is_some());
+        assert!(type_3.unwrap().reason_cells.contains(&15));
+        assert_eq!(type_3.unwrap().eliminations, vec![(10, 0b1100), (11, 0b1100), (13, 0b1100), (14, 0b1100), (16, 0b1100), (17, 0b1100)]);
+    }
+    #[test]
+    fn test_unique_rectangle_type_4() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // r1c1 and r1c4 only 1 or 2, within row 2 value 1 only in r2c1 and r2c4
+        for idx in [0, 3].iter() {
+            bref.get_cell(*idx).reduce(0b111111100).ok();
+        }
+        for cell in &bref.rows[1].get_cells()[..] {
+            if cell.get_idx() != 9 && cell.get_idx() != 12 {
+                cell.reduce(0b000000001).ok();
+            }
+        }
+        let result = find_unique_rectangle(bref);
+        let type_4 = result.iter().find(|d| d.technique == "Unique Rectangle Type 4");
+        assert!(type_4.is_some());
+        assert_eq!(type_4.unwrap().eliminations, vec![(9, 0b000000010), (12, 0b000000010)]);
+    }
+    #[test]
+    fn test_unique_rectangle_type_5() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // r1c1 and r2c4 only 1 or 2, the diagonal r1c4 and r2c1 only 1, 2 or 3: one of them is 3
+        for idx in [0, 12].iter() {
+            keep(bref.get_cell(*idx), 0b011);
+        }
+        for idx in [3, 9].iter() {
+            keep(bref.get_cell(*idx), 0b111);
+        }
+        let result = find_unique_rectangle(bref);
+        let type_5 = result.iter().find(|d| d.technique == "Unique Rectangle Type 5");
+        assert!(type_5.is_some());
+        let mut eliminations = type_5.unwrap().eliminations.clone();
+        eliminations.sort();
+        assert_eq!(eliminations, vec![(1, 0b100), (2, 0b100), (13, 0b100), (14, 0b100)]);
+    }
+    #[test]
+    fn test_unique_rectangle_type_6() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // r1c1 and r2c4 only 1 or 2, the diagonal r1c4 {1234} and r2c1 {125}, in rows 1 and 2 value 1 only in the rectangle
+        for idx in [0, 12].iter() {
+            keep(bref.get_cell(*idx), 0b00011);
+        }
+        keep(bref.get_cell(3), 0b01111);
+        keep(bref.get_cell(9), 0b10011);
+        only_at(&bref.rows[0], &[0, 3]);
+        only_at(&bref.rows[1], &[0, 3]);
+        let result = find_unique_rectangle(bref);
+        let type_6 = result.iter().find(|d| d.technique == "Unique Rectangle Type 6");
+        assert!(type_6.is_some());
+        assert_eq!(type_6.unwrap().eliminations, vec![(3, 0b001), (9, 0b001)]);
+    }
+    #[test]
+    fn test_bug_plus_one() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // all open cells bivalue except r7c6 {348}: without 3 they form a grave, 3 is three times in its row, col and square
+        let solution = "946531827357826149182947536269415378513768492478392615694283751835179264721654983";
+        let open:[(usize, usize);15] = [(1, 0b000001100), (2, 0b000101000), (4, 0b000100100), (9, 0b000100100),
+                                        (14, 0b000100100), (40, 0b010100000), (41, 0b010100000), (54, 0b000100100),
+                                        (56, 0b000101000), (58, 0b010000100), (59, 0b010001100), (64, 0b000001100),
+                                        (71, 0b000001100), (77, 0b000001100), (80, 0b000001100)];
+        for (idx, c) in solution.chars().enumerate() {
+            match open.iter().find(|o| o.0 == idx) {
+                Some((_, mask)) => keep(bref.get_cell(idx), *mask),
+                None            => bref.get_cell(idx).set_init_value(c.to_digit(10).unwrap() as usize),
+            }
+        }
+        let result = find_bug_plus_one(bref);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].placements, vec![(59, 0b000000100)]);
+        assert_eq!(result[0].units, vec![6, 14, 25]);
+        assert_eq!(result[0].reason_cells.len(), 15);
+        assert_eq!(result[0].reason_cells[0], 59);
+    }
+    #[test]
+    fn test_no_bug_plus_one() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        // r1c1 {123}, value 1 also in two other cells of its row, col and square, all other open cells bivalue:
+        // without 1 r1c1 is {23}, but row 1 still holds 1 twice and 2 once, so the cells do not form a grave
+        let open:[(usize, usize);7] = [(0, 0b000000111), (4, 0b000001001), (8, 0b000010001), (36, 0b000100001),
+                                       (72, 0b001000001), (10, 0b010000001), (20, 0b100000001)];
+        for cell in bref.all_cells() {
+            match open.iter().find(|o| o.0 == cell.get_idx()) {
+                Some((_, mask)) => { cell.reduce(0b111111111 & !*mask).ok(); },
+                None            => cell.set_init_value(9),
+            }
+        }
+        assert!(find_bug_plus_one(bref).is_empty());
+    }
+}
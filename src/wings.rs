@@ -0,0 +1,172 @@
+use crate::sudoku_board::*;
+use crate::sudoku_state::*;
+use crate::deduction::*;
+
+/**
+ * XY-Wing: a pivot cell with the values XY sees two pincers with the values XZ and YZ.
+ * Whatever the pivot will be, one of the pincers will be Z. So Z can not be placed in a cell which sees both pincers.
+ */
+pub fn find_xy_wing(board:&SudokuBoard) -> Vec<Deduction> {
+    let mut result = vec![];
+    for pivot in board.all_cells() {
+        let pivot_mask = pivot.get_candidates();
+        if pivot_mask.count_ones() != 2 {
+            continue;
+        }
+        let pincers = bivalue_peers(board, pivot);
+        for pair in combinations(&(0..pincers.len()).collect::<Vec<usize>>(), 2) {
+            let (a, b) = (&pincers[pair[0]], &pincers[pair[1]]);
+            let (a_mask, b_mask) = (a.get_candidates(), b.get_candidates());
+            let z_mask = a_mask & b_mask;
+            if z_mask.count_ones() != 1 || z_mask & pivot_mask != 0 || (a_mask | b_mask) & !z_mask != pivot_mask {
+                continue;
+            }
+            let deduction = wing_deduction("XY-Wing", board, &[pivot, a, b], &[a, b], z_mask);
+            if !deduction.is_empty() {
+                result.push(deduction);
+            }
+        }
+    }
+    result
+}
+
+/**
+ * XYZ-Wing: a pivot cell with the values XYZ sees two pincers with the values XZ and YZ.
+ * One of the three cells will be Z. So Z can not be placed in a cell which sees all three of them.
+ */
+pub fn find_xyz_wing(board:&SudokuBoard) -> Vec<Deduction> {
+    let mut result = vec![];
+    for pivot in board.all_cells() {
+        let pivot_mask = pivot.get_candidates();
+        if pivot_mask.count_ones() != 3 {
+            continue;
+        }
+        let pincers = bivalue_peers(board, pivot);
+        for pair in combinations(&(0..pincers.len()).collect::<Vec<usize>>(), 2) {
+            let (a, b) = (&pincers[pair[0]], &pincers[pair[1]]);
+            let (a_mask, b_mask) = (a.get_candidates(), b.get_candidates());
+            let z_mask = a_mask & b_mask;
+            if z_mask.count_ones() != 1 || a_mask | b_mask != pivot_mask {
+                continue;
+            }
+            let deduction = wing_deduction("XYZ-Wing", board, &[pivot, a, b], &[pivot, a, b], z_mask);
+            if !deduction.is_empty() {
+                result.push(deduction);
+            }
+        }
+    }
+    result
+}
+
+/**
+ * W-Wing: two cells with the same values XY which do not see each other, connected by a strong link on X.
+ * The strong link is a row, col or square in which X can only be placed in two cells, each seeing one of the wing cells.
+ * One of the wing cells will be Y, so Y can not be placed in a cell which sees both wing cells.
+ */
+pub fn find_w_wing(board:&SudokuBoard) -> Vec<Deduction> {
+    let mut result = vec![];
+    let bivalues:Vec<&RcSudokuCell> = board.all_cells().iter()
+        .filter(|cell| cell.get_candidates().count_ones() == 2)
+        .collect();
+    for pair in combinations(&(0..bivalues.len()).collect::<Vec<usize>>(), 2) {
+        let (a, b) = (bivalues[pair[0]], bivalues[pair[1]]);
+        let mask = a.get_candidates();
+        if mask != b.get_candidates() || a.sees(b) {
+            continue;
+        }
+        // try both values of the wing cells as X: the lowest and the highest bit of the mask
+        for x_mask in [mask & mask.wrapping_neg(), mask & (mask - 1)].iter() {
+            let y_mask = mask & !x_mask;
+            for unit in board.all_logic_squares() {
+                let link = unit.get_value_cells(*x_mask);
+                if link.len() != 2 || unit.contains(a) || unit.contains(b) {
+                    continue;
+                }
+                let (c, d) = if link[0].sees(a) && link[1].sees(b) {
+                    (&link[0], &link[1])
+                } else if link[1].sees(a) && link[0].sees(b) {
+                    (&link[1], &link[0])
+                } else {
+                    continue;
+                };
+                let deduction = wing_deduction("W-Wing", board, &[a, c, d, b], &[a, b], y_mask);
+                if !deduction.is_empty() {
+                    result.push(deduction);
+                    break;
+                }
+            }
+        }
+    }
+    result
+}
+
+// the unresolved peers of a cell which have exactly two possible values
+fn bivalue_peers(board:&SudokuBoard, cell:&RcSudokuCell) -> Vec<RcSudokuCell> {
+    board.get_peers(cell).into_iter()
+        .filter(|peer| peer.get_candidates().count_ones() == 2)
+        .collect()
+}
+
+// remove the value from all cells which see each of the seen cells, the units are the ones shared by wing cells
+fn wing_deduction(name:&str, board:&SudokuBoard, wing:&[&RcSudokuCell], seen:&[&RcSudokuCell], value_mask:usize) -> Deduction {
+    let mut deduction = Deduction::new(name);
+    for cell in wing {
+        deduction.reason_cells.push(cell.get_idx());
+    }
+    for pair in combinations(&deduction.reason_cells.clone(), 2) {
+        deduction.add_units(&common_units(&pair));
+    }
+    for cell in board.common_peers(seen) {
+        if cell.get_candidates() & value_mask != 0 {
+            deduction.eliminate(cell.get_idx(), value_mask);
+        }
+    }
+    deduction
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+
+    #[test]
+    fn test_xy_wing() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        keep(&bref.rows[0].cells[0], 0b011);
+        keep(&bref.rows[0].cells[4], 0b101);
+        keep(&bref.rows[4].cells[0], 0b110);
+        let result = find_xy_wing(bref);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].eliminations, vec![(40, 0b100)]);
+        assert_eq!(result[0].units, vec![0, 9]);
+    }
+    #[test]
+    fn test_xyz_wing() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        keep(&bref.rows[0].cells[0], 0b111);
+        keep(&bref.rows[0].cells[1], 0b101);
+        keep(&bref.rows[1].cells[0], 0b110);
+        let result = find_xyz_wing(bref);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].eliminations.len(), 6);
+        assert!(result[0].eliminations.iter().all(|e| e.1 == 0b100));
+    }
+    #[test]
+    fn test_w_wing() { 
+        let  mut board = SudokuBoard::new();
+        let bref = board.wire();
+        keep(&bref.rows[0].cells[0], 0b011);
+        keep(&bref.rows[4].cells[8], 0b011);
+        // in row 3 value 1 can only be placed in col 1 and col 9
+        for cell in &bref.rows[2].cells[1..8] {
+            cell.reduce(0b001).ok();
+        }
+        let result = find_w_wing(bref);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].reason_cells, vec![0, 18, 26, 44]);
+        assert_eq!(result[0].eliminations, vec![(8, 0b010), (36, 0b010)]);
+    }
+}